@@ -1,17 +1,15 @@
-use criterion::{BenchmarkId, Criterion, black_box, criterion_group, criterion_main};
+use criterion::{
+    BenchmarkGroup, BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main,
+    measurement::WallTime,
+};
 use std::collections::HashMap;
 
-// We need to include the modules since this is a benchmark
-mod four {
-    include!("../src/four.rs");
-}
-
-mod minmax {
-    include!("../src/minmax.rs");
-}
-
-use four::{BOARD_WIDTH, FourRow, GameAction, GameResult, Player};
-use minmax::{GameState, minmax};
+use fourrow::checkers::CheckersBoard;
+use fourrow::four::{FourRow, GameAction};
+use fourrow::four_bitboard::FourRowBitboard;
+use fourrow::minmax::{
+    self, GameResult, GameState, MakeMove, Player, TranspositionTable, minmax, minmax_in_place,
+};
 
 fn create_empty_game() -> FourRow {
     FourRow::new()
@@ -169,12 +167,12 @@ fn bench_minimax_depth(c: &mut Criterion) {
     for depth in 1..=6 {
         group.bench_with_input(BenchmarkId::new("depth", depth), &depth, |b, &depth| {
             b.iter(|| {
-                let mut explored_states = HashMap::new();
+                let mut table = TranspositionTable::new();
                 black_box(minmax(
-                    game.clone(),
+                    &game,
                     depth,
                     true,
-                    &mut explored_states,
+                    &mut table,
                     f32::NEG_INFINITY,
                     f32::INFINITY,
                 ))
@@ -184,12 +182,12 @@ fn bench_minimax_depth(c: &mut Criterion) {
 
     group.bench_with_input(BenchmarkId::new("depth", 12), &12, |b, &depth| {
         b.iter(|| {
-            let mut explored_states = HashMap::new();
+            let mut table = TranspositionTable::new();
             black_box(minmax(
-                game.clone(),
+                &game,
                 depth,
                 true,
-                &mut explored_states,
+                &mut table,
                 f32::NEG_INFINITY,
                 f32::INFINITY,
             ))
@@ -213,12 +211,12 @@ fn bench_minimax_positions(c: &mut Criterion) {
         &empty_game,
         |b, game| {
             b.iter(|| {
-                let mut explored_states = HashMap::new();
+                let mut table = TranspositionTable::new();
                 black_box(minmax(
-                    game.clone(),
+                    game,
                     depth,
                     true,
-                    &mut explored_states,
+                    &mut table,
                     f32::NEG_INFINITY,
                     f32::INFINITY,
                 ))
@@ -231,12 +229,12 @@ fn bench_minimax_positions(c: &mut Criterion) {
         &early_game,
         |b, game| {
             b.iter(|| {
-                let mut explored_states = HashMap::new();
+                let mut table = TranspositionTable::new();
                 black_box(minmax(
-                    game.clone(),
+                    game,
                     depth,
                     true,
-                    &mut explored_states,
+                    &mut table,
                     f32::NEG_INFINITY,
                     f32::INFINITY,
                 ))
@@ -246,12 +244,12 @@ fn bench_minimax_positions(c: &mut Criterion) {
 
     group.bench_with_input(BenchmarkId::new("mid_game", depth), &mid_game, |b, game| {
         b.iter(|| {
-            let mut explored_states = HashMap::new();
+            let mut table = TranspositionTable::new();
             black_box(minmax(
-                game.clone(),
+                game,
                 depth,
                 true,
-                &mut explored_states,
+                &mut table,
                 f32::NEG_INFINITY,
                 f32::INFINITY,
             ))
@@ -274,7 +272,7 @@ fn bench_game_clone(c: &mut Criterion) {
 fn bench_hash_performance(c: &mut Criterion) {
     let mut group = c.benchmark_group("hash_performance");
 
-    let games = vec![
+    let games = [
         create_empty_game(),
         create_early_game(),
         create_mid_game(),
@@ -291,6 +289,16 @@ fn bench_hash_performance(c: &mut Criterion) {
         })
     });
 
+    group.bench_function("hash_keys", |b| {
+        b.iter(|| {
+            let mut map = minmax::KeyMap::default();
+            for (i, game) in games.iter().enumerate() {
+                map.insert(black_box(game).hash_key(), i);
+            }
+            black_box(map)
+        })
+    });
+
     group.finish();
 }
 
@@ -303,20 +311,20 @@ fn bench_full_game_simulation(c: &mut Criterion) {
             let mut move_count = 0;
 
             while game.get_terminal() == GameResult::Ongoing && move_count < 42 {
-                let mut explored_states = HashMap::new();
+                let mut table = TranspositionTable::new();
                 let is_maximizing = game.current_player == Player::Player1;
 
                 let result = minmax(
-                    game.clone(),
+                    &game,
                     3,
                     is_maximizing,
-                    &mut explored_states,
+                    &mut table,
                     f32::NEG_INFINITY,
                     f32::INFINITY,
                 );
 
-                if let Some(best_state) = result.best_child(is_maximizing, &explored_states) {
-                    game = best_state.clone();
+                if let Some(action) = result.best_action {
+                    game = game.apply_action(&action);
                     move_count += 1;
                 } else {
                     break;
@@ -329,6 +337,225 @@ fn bench_full_game_simulation(c: &mut Criterion) {
     group.finish();
 }
 
+/// Runs the same operations on the array board and the bitboard.
+fn bench_bitboard_comparison(c: &mut Criterion) {
+    let positions = [
+        ("empty_game", create_empty_game()),
+        ("mid_game", create_mid_game()),
+        ("complex_game", create_complex_game()),
+    ];
+
+    let mut group = c.benchmark_group("bitboard_terminal_check");
+    for (name, game) in &positions {
+        let bitboard = FourRowBitboard::try_from(game).unwrap();
+        group.bench_with_input(BenchmarkId::new("array", name), game, |b, game| {
+            b.iter(|| black_box(game.get_terminal()))
+        });
+        group.bench_with_input(BenchmarkId::new("bitboard", name), &bitboard, |b, game| {
+            b.iter(|| black_box(game.get_terminal()))
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("bitboard_evaluation");
+    for (name, game) in &positions {
+        let bitboard = FourRowBitboard::try_from(game).unwrap();
+        group.bench_with_input(BenchmarkId::new("array", name), game, |b, game| {
+            b.iter(|| black_box(game.evaluate()))
+        });
+        group.bench_with_input(BenchmarkId::new("bitboard", name), &bitboard, |b, game| {
+            b.iter(|| black_box(game.evaluate()))
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("bitboard_move_application");
+    let game = create_early_game();
+    let bitboard = FourRowBitboard::try_from(&game).unwrap();
+    let action = GameAction { column: 4 };
+    group.bench_function("array", |b| {
+        b.iter(|| black_box(game.apply_action(&action)))
+    });
+    group.bench_function("bitboard", |b| {
+        b.iter(|| black_box(bitboard.apply_action(&action)))
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("bitboard_minimax");
+    let depth = 6;
+    group.bench_with_input(BenchmarkId::new("array", depth), &game, |b, game| {
+        b.iter(|| {
+            let mut table = TranspositionTable::new();
+            black_box(minmax(
+                game,
+                depth,
+                true,
+                &mut table,
+                f32::NEG_INFINITY,
+                f32::INFINITY,
+            ))
+        })
+    });
+    group.bench_with_input(BenchmarkId::new("bitboard", depth), &bitboard, |b, game| {
+        b.iter(|| {
+            let mut table = TranspositionTable::new();
+            black_box(minmax(
+                game,
+                depth,
+                true,
+                &mut table,
+                f32::NEG_INFINITY,
+                f32::INFINITY,
+            ))
+        })
+    });
+    group.finish();
+}
+
+/// Compares the sequential search with the root-splitting parallel one on
+/// all available cores.
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+fn bench_parallel_speedup(c: &mut Criterion) {
+    use fourrow::parallel::{ParallelConfig, parallel_minmax};
+
+    let mut group = c.benchmark_group("parallel_speedup");
+    group.sample_size(10);
+
+    let game = FourRowBitboard::try_from(&create_early_game()).unwrap();
+    let depth = 10;
+    let config = ParallelConfig::default();
+
+    group.bench_with_input(BenchmarkId::new("minmax", depth), &game, |b, game| {
+        b.iter(|| {
+            let mut table = TranspositionTable::new();
+            black_box(minmax_in_place(
+                game,
+                depth,
+                true,
+                &mut table,
+                f32::NEG_INFINITY,
+                f32::INFINITY,
+            ))
+        })
+    });
+    group.bench_with_input(
+        BenchmarkId::new(format!("parallel_{}_threads", config.threads), depth),
+        &game,
+        |b, game| b.iter(|| black_box(parallel_minmax(game, depth, true, &config))),
+    );
+    group.finish();
+}
+
+#[cfg(not(all(feature = "parallel", not(target_arch = "wasm32"))))]
+fn bench_parallel_speedup(_c: &mut Criterion) {}
+
+/// Counts the nodes of the full game tree down to `depth`, copying the
+/// position for every child.
+fn walk_copying<T: GameState>(state: &T, depth: u8) -> u64 {
+    if depth == 0 || state.is_terminal() {
+        return 1;
+    }
+    1 + state
+        .get_possible_actions()
+        .iter()
+        .map(|action| walk_copying(&state.apply_action(action), depth - 1))
+        .sum::<u64>()
+}
+
+/// `walk_copying` with moves made and taken back in place.
+fn walk_in_place<T: MakeMove>(state: &mut T, depth: u8) -> u64 {
+    if depth == 0 || state.is_terminal() {
+        return 1;
+    }
+    let mut nodes = 1;
+    for action in state.get_possible_actions() {
+        let undo = state.make_move(&action);
+        nodes += walk_in_place(state, depth - 1);
+        state.unmake_move(undo);
+    }
+    nodes
+}
+
+fn bench_walks<T: MakeMove + Clone>(
+    group: &mut BenchmarkGroup<WallTime>,
+    name: &str,
+    state: &T,
+    depth: u8,
+) {
+    group.throughput(Throughput::Elements(walk_copying(state, depth)));
+    group.bench_with_input(
+        BenchmarkId::new(format!("{}_apply_action", name), depth),
+        state,
+        |b, state| b.iter(|| black_box(walk_copying(state, depth))),
+    );
+    group.bench_with_input(
+        BenchmarkId::new(format!("{}_make_move", name), depth),
+        state,
+        |b, state| b.iter(|| black_box(walk_in_place(&mut state.clone(), depth))),
+    );
+}
+
+fn bench_searches<T: MakeMove + Clone>(
+    group: &mut BenchmarkGroup<WallTime>,
+    name: &str,
+    state: &T,
+    depth: u8,
+) {
+    group.bench_with_input(
+        BenchmarkId::new(format!("{}_minmax", name), depth),
+        state,
+        |b, state| {
+            b.iter(|| {
+                let mut table = TranspositionTable::new();
+                black_box(minmax(
+                    state,
+                    depth,
+                    true,
+                    &mut table,
+                    f32::NEG_INFINITY,
+                    f32::INFINITY,
+                ))
+            })
+        },
+    );
+    group.bench_with_input(
+        BenchmarkId::new(format!("{}_minmax_in_place", name), depth),
+        state,
+        |b, state| {
+            b.iter(|| {
+                let mut table = TranspositionTable::new();
+                black_box(minmax_in_place(
+                    state,
+                    depth,
+                    true,
+                    &mut table,
+                    f32::NEG_INFINITY,
+                    f32::INFINITY,
+                ))
+            })
+        },
+    );
+}
+
+/// `apply_action` against `make_move` and `unmake_move`, as nodes per
+/// second of a plain tree walk and as time per search.
+fn bench_make_unmake(c: &mut Criterion) {
+    let four = create_early_game();
+    let checkers = CheckersBoard::new();
+
+    let mut group = c.benchmark_group("make_unmake_walk");
+    group.sample_size(10);
+    bench_walks(&mut group, "four", &four, 5);
+    bench_walks(&mut group, "checkers", &checkers, 6);
+    group.finish();
+
+    let mut group = c.benchmark_group("make_unmake_search");
+    group.sample_size(10);
+    bench_searches(&mut group, "four", &four, 7);
+    bench_searches(&mut group, "checkers", &checkers, 7);
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_game_creation,
@@ -340,7 +567,10 @@ criterion_group!(
     bench_minimax_positions,
     bench_game_clone,
     bench_hash_performance,
-    bench_full_game_simulation
+    bench_full_game_simulation,
+    bench_bitboard_comparison,
+    bench_parallel_speedup,
+    bench_make_unmake
 );
 
 criterion_main!(benches);
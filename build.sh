@@ -11,7 +11,7 @@ if ! command -v wasm-pack &> /dev/null; then
 fi
 
 # Build the WebAssembly module
-wasm-pack build --target web --out-dir webpage/pkg
+wasm-pack build --target web --out-dir webpage/pkg -- --features wasm
 
 echo "Build complete!"
 echo ""
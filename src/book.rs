@@ -0,0 +1,392 @@
+//! Opening books mapping positions to good moves
+//!
+//! A book is built offline by searching every position up to a few plies
+//! from the start deeply with `minmax::analyze`, and keeping the moves that
+//! come close to the best one. Each move gets a weight from 1 to
+//! `MAX_WEIGHT` by how close it came, so the engine can either always play
+//! the best book move or pick one at random in proportion to the weights.
+//!
+//! Positions are looked up by `GameState::hash_key` and moves are stored as
+//! their index in `get_possible_actions`. A book therefore only fits the
+//! board type it was built with, and has to be rebuilt when the keys or the
+//! move order of that type change.
+//!
+//! The file format is little-endian: the magic bytes `FRBK`, a version
+//! byte and the number of positions as a `u32`, then for every position
+//! its key as a `u64`, the number of moves as a `u8` and every move as a
+//! `u8` index and a `u16` weight.
+
+use std::{fmt::Display, sync::OnceLock};
+
+use crate::minmax::{self, GameState, KeyMap, MakeMove};
+
+const MAGIC: &[u8; 4] = b"FRBK";
+const VERSION: u8 = 1;
+
+/// Weight of the best move in a position.
+pub const MAX_WEIGHT: u16 = 100;
+
+/// Book for the classic Connect Four board, keyed by `FourRowBitboard`.
+static CONNECT_FOUR_BOOK: &[u8] = include_bytes!("../books/connect-four.book");
+
+/// Book for checkers from the standard start position.
+static CHECKERS_BOOK: &[u8] = include_bytes!("../books/checkers.book");
+
+/// Error when reading a book file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookError {
+    /// The data does not start with the book magic bytes.
+    NotABook,
+    /// The book was written by a newer version of the format.
+    UnsupportedVersion(u8),
+    /// The data ends in the middle of an entry.
+    Truncated,
+}
+
+impl Display for BookError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BookError::NotABook => write!(f, "not an opening book"),
+            BookError::UnsupportedVersion(version) => {
+                write!(f, "unsupported book version {}", version)
+            }
+            BookError::Truncated => write!(f, "book data ends unexpectedly"),
+        }
+    }
+}
+
+impl std::error::Error for BookError {}
+
+/// How to choose among the book moves of a position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookChoice {
+    /// The move with the highest weight, the first one on ties.
+    Best,
+    /// A move at random in proportion to its weight, using the given
+    /// random number.
+    Weighted(u64),
+}
+
+/// Settings of `build`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BookConfig {
+    /// Positions up to this many plies after the root get book moves.
+    pub plies: u8,
+    /// Depth of the search valuing the moves of each position.
+    pub depth: u8,
+    /// How much worse than the best move a move may be and still go in the
+    /// book, in evaluation units.
+    pub margin: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct BookMove {
+    /// Index in `get_possible_actions`.
+    index: u8,
+    weight: u16,
+}
+
+/// Weighted moves for positions, by key.
+#[derive(Debug, Clone, Default)]
+pub struct OpeningBook {
+    entries: KeyMap<Vec<BookMove>>,
+}
+
+impl OpeningBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of positions in the book.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The book moves of `state` with their weights, best first. Empty if
+    /// the position is not in the book.
+    pub fn moves<T: GameState>(&self, state: &T) -> Vec<(T::Action, u16)> {
+        let Some(entry) = self.entries.get(&state.hash_key()) else {
+            return Vec::new();
+        };
+        let actions = state.get_possible_actions();
+        // A key collision can point at indices the position does not have
+        entry
+            .iter()
+            .filter_map(|book_move| {
+                let action = actions.get(book_move.index as usize)?;
+                Some((action.clone(), book_move.weight))
+            })
+            .collect()
+    }
+
+    /// A book move for `state`, or `None` if it is out of book.
+    pub fn choose<T: GameState>(&self, state: &T, choice: BookChoice) -> Option<T::Action> {
+        let moves = self.moves(state);
+        let total: u64 = moves.iter().map(|(_, weight)| *weight as u64).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut target = match choice {
+            BookChoice::Best => 0,
+            BookChoice::Weighted(random) => random % total,
+        };
+        moves.into_iter().find_map(|(action, weight)| {
+            if target < weight as u64 {
+                Some(action)
+            } else {
+                target -= weight as u64;
+                None
+            }
+        })
+    }
+
+    /// Reads a book written by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BookError> {
+        let mut reader = Reader(bytes);
+        if reader.take(4)? != MAGIC {
+            return Err(BookError::NotABook);
+        }
+        let version = reader.take(1)?[0];
+        if version != VERSION {
+            return Err(BookError::UnsupportedVersion(version));
+        }
+
+        let count = u32::from_le_bytes(reader.array()?);
+        let mut entries = KeyMap::default();
+        for _ in 0..count {
+            let key = u64::from_le_bytes(reader.array()?);
+            let moves = (0..reader.take(1)?[0])
+                .map(|_| {
+                    let index = reader.take(1)?[0];
+                    let weight = u16::from_le_bytes(reader.array()?);
+                    Ok(BookMove { index, weight })
+                })
+                .collect::<Result<_, BookError>>()?;
+            entries.insert(key, moves);
+        }
+        Ok(OpeningBook { entries })
+    }
+
+    /// The book in the file format, positions sorted by key so the same
+    /// book always gives the same bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut keys: Vec<_> = self.entries.keys().copied().collect();
+        keys.sort_unstable();
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&(keys.len() as u32).to_le_bytes());
+        for key in keys {
+            let moves = &self.entries[&key];
+            bytes.extend_from_slice(&key.to_le_bytes());
+            bytes.push(moves.len() as u8);
+            for book_move in moves {
+                bytes.push(book_move.index);
+                bytes.extend_from_slice(&book_move.weight.to_le_bytes());
+            }
+        }
+        bytes
+    }
+}
+
+/// Reads the book file format front to back.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], BookError> {
+        if self.0.len() < len {
+            return Err(BookError::Truncated);
+        }
+        let (head, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], BookError> {
+        Ok(self.take(N)?.try_into().expect("took N bytes"))
+    }
+}
+
+/// Builds a book for every position reachable in fewer than
+/// `config.plies` plies from `root`, `maximizing_player` being true if
+/// Player1 is to move there.
+///
+/// Every move of every position is followed, not just the book moves, so
+/// the book still applies after the opponent leaves it for a ply. Moves
+/// within `config.margin` of the best get weights falling linearly from
+/// `MAX_WEIGHT` to 1.
+pub fn build<T: MakeMove + Clone>(
+    root: &T,
+    maximizing_player: bool,
+    config: &BookConfig,
+) -> OpeningBook {
+    let mut book = OpeningBook::new();
+    let mut frontier = vec![root.clone()];
+    let mut maximizing_player = maximizing_player;
+
+    for _ in 0..config.plies {
+        let mut next_frontier = Vec::new();
+        for state in frontier {
+            let key = state.hash_key();
+            if state.is_terminal() || book.entries.contains_key(&key) {
+                continue;
+            }
+
+            let analysis = minmax::analyze(&state, config.depth, maximizing_player);
+            let sign = if maximizing_player { 1.0 } else { -1.0 };
+            let best = sign * analysis[0].evaluation;
+            let actions = state.get_possible_actions();
+            let moves = analysis
+                .iter()
+                .filter_map(|entry| {
+                    let gap = best - sign * entry.evaluation;
+                    let weight = if gap <= 0.0 {
+                        MAX_WEIGHT
+                    } else if gap <= config.margin {
+                        let closeness = 1.0 - gap / config.margin;
+                        1 + (closeness * (MAX_WEIGHT - 1) as f32).round() as u16
+                    } else {
+                        return None;
+                    };
+                    let index = actions.iter().position(|action| *action == entry.action)?;
+                    Some(BookMove {
+                        index: index as u8,
+                        weight,
+                    })
+                })
+                .collect();
+            book.entries.insert(key, moves);
+
+            next_frontier.extend(actions.iter().map(|action| state.apply_action(action)));
+        }
+        frontier = next_frontier;
+        maximizing_player = !maximizing_player;
+    }
+    book
+}
+
+/// The built-in book for the classic Connect Four board. Positions have to
+/// be looked up as `FourRowBitboard`s.
+pub fn connect_four() -> &'static OpeningBook {
+    static BOOK: OnceLock<OpeningBook> = OnceLock::new();
+    BOOK.get_or_init(|| {
+        OpeningBook::from_bytes(CONNECT_FOUR_BOOK).expect("the built-in book is valid")
+    })
+}
+
+/// The built-in checkers book.
+pub fn checkers() -> &'static OpeningBook {
+    static BOOK: OnceLock<OpeningBook> = OnceLock::new();
+    BOOK.get_or_init(|| OpeningBook::from_bytes(CHECKERS_BOOK).expect("the built-in book is valid"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::CheckersBoard;
+    use crate::four::{FourRow, GameAction};
+    use crate::four_bitboard::FourRowBitboard;
+
+    fn small_book() -> OpeningBook {
+        let config = BookConfig {
+            plies: 2,
+            depth: 4,
+            margin: 5.0,
+        };
+        build(&FourRow::new(), true, &config)
+    }
+
+    #[test]
+    fn test_build_covers_every_reply() {
+        let book = small_book();
+        // The empty board and all seven replies to it
+        assert_eq!(book.len(), 8);
+        let state = FourRow::new();
+        for action in state.get_possible_actions() {
+            assert!(!book.moves(&state.apply_action(&action)).is_empty());
+        }
+
+        let moves = book.moves(&state);
+        assert_eq!(moves[0].1, MAX_WEIGHT);
+        assert!(
+            moves
+                .iter()
+                .all(|(_, weight)| (1..=MAX_WEIGHT).contains(weight))
+        );
+        assert!(book.moves(&FourRow::from_moves("44").unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let book = small_book();
+        let bytes = book.to_bytes();
+        let read = OpeningBook::from_bytes(&bytes).unwrap();
+        assert_eq!(read.to_bytes(), bytes);
+        let state = FourRow::from_moves("3").unwrap();
+        assert_eq!(read.moves(&state), book.moves(&state));
+
+        assert_eq!(
+            OpeningBook::from_bytes(b"PK\x03\x04").unwrap_err(),
+            BookError::NotABook
+        );
+        assert_eq!(
+            OpeningBook::from_bytes(b"FRBK\x09").unwrap_err(),
+            BookError::UnsupportedVersion(9)
+        );
+        assert_eq!(
+            OpeningBook::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err(),
+            BookError::Truncated
+        );
+    }
+
+    #[test]
+    fn test_weighted_choice() {
+        let mut book = OpeningBook::new();
+        let state = FourRow::new();
+        book.entries.insert(
+            state.hash_key(),
+            vec![
+                BookMove {
+                    index: 3,
+                    weight: 3,
+                },
+                BookMove {
+                    index: 2,
+                    weight: 1,
+                },
+            ],
+        );
+
+        let column = |choice| book.choose(&state, choice).map(|action| action.column);
+        assert_eq!(column(BookChoice::Best), Some(3));
+        let picks: Vec<_> = (0..8).map(|r| column(BookChoice::Weighted(r))).collect();
+        let centre = picks.iter().filter(|&&pick| pick == Some(3)).count();
+        assert_eq!(centre, 6);
+        assert!(picks.contains(&Some(2)));
+        assert_eq!(
+            book.choose(&FourRow::from_moves("1").unwrap(), BookChoice::Best),
+            None
+        );
+    }
+
+    #[test]
+    fn test_built_in_books() {
+        let start = FourRowBitboard::try_from(&FourRow::new()).unwrap();
+        // The centre column is the only winning first move
+        assert_eq!(
+            connect_four().choose(&start, BookChoice::Best),
+            Some(GameAction { column: 3 })
+        );
+
+        let start = CheckersBoard::new();
+        let legal = start.get_possible_actions();
+        let moves = checkers().moves(&start);
+        assert!(!moves.is_empty());
+        assert!(moves.iter().all(|(action, _)| legal.contains(action)));
+    }
+}
@@ -1,5 +1,5 @@
 //! Checkers game implementation using the GameState trait
-//! 
+//!
 //! This module implements a complete checkers game with the following rules:
 //! - Checkers can move diagonally forward one square
 //! - Checkers can capture opponent pieces by jumping over them (exactly 2 squares diagonally)
@@ -7,17 +7,47 @@
 //! - All captures are mandatory (if a capture is possible, it must be taken)
 //! - Checkers promote to queens when reaching the opposite end
 //! - Queens can move diagonally in any direction
-//! - Game ends when: one player has no pieces, no captures for 50 moves, or no moves available
+//! - Game ends when: one player has no pieces, no captures for 50 moves, the same position
+//!   occurs for the third time, or no moves available
+//!
+//! These are the English rules. A `RulesConfig` changes them for other draughts variants,
+//! with presets for Russian, Brazilian, pool and Italian checkers.
 //!
 //! ## Action Format
-//! 
+//!
 //! Actions are represented as `Vec<(usize, usize, usize, usize)>` where each tuple represents
 //! a single move from (from_row, from_col) to (to_row, to_col). For regular moves, the vector
 //! contains one element. For multiple captures, the vector contains multiple elements representing
 //! the sequence of jumps.
 
-use crate::minmax::{GameState, Player};
+use crate::minmax::{GameState, MakeMove, Player, zobrist_keys};
 use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+
+/// Zobrist keys for each kind of piece on every square.
+const PIECE_KEYS: [u64; 8 * 8 * 4] = zobrist_keys(0x4348_4543_4b45_5253);
+
+/// Part of the key while Player2 is to move.
+const PLAYER2_KEY: u64 = zobrist_keys::<1>(0x0050_4c41_5945_5232)[0];
+
+/// Directions along the diagonals, as row and column steps.
+const DIAGONALS: [(i32, i32); 4] = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
+
+/// Bit of a square in a set of squares held in a `u64`.
+fn square_bit(row: usize, col: usize) -> u64 {
+    1 << (row * 8 + col)
+}
+
+fn piece_key(row: usize, col: usize, tile: CheckersTile) -> u64 {
+    let kind = match tile {
+        CheckersTile::Empty => return 0,
+        CheckersTile::Checker(Player::Player1) => 0,
+        CheckersTile::Checker(Player::Player2) => 1,
+        CheckersTile::Queen(Player::Player1) => 2,
+        CheckersTile::Queen(Player::Player2) => 3,
+    };
+    PIECE_KEYS[(row * 8 + col) * 4 + kind]
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum CheckersTile {
@@ -26,11 +56,161 @@ pub enum CheckersTile {
     Queen(Player),
 }
 
-#[derive(Clone, Hash, PartialEq, Eq)]
+/// What happens to a checker that reaches the far row in the middle of a
+/// capture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Promotion {
+    /// It is crowned and the move ends there.
+    EndsCapture,
+    /// It is crowned and captures on as a king.
+    ContinuesAsKing,
+    /// It captures on as a checker and is only crowned if the move ends on
+    /// the far row.
+    AtEnd,
+}
+
+/// The draughts rules a board is played by. The default is English checkers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RulesConfig {
+    /// Kings move and capture any distance along a diagonal.
+    pub flying_kings: bool,
+    /// Checkers capture backwards as well as forwards.
+    pub men_capture_backwards: bool,
+    /// Checkers may capture kings.
+    pub men_capture_kings: bool,
+    /// The capture taking the most pieces must be chosen.
+    pub majority_capture: bool,
+    /// Of the captures left, one by a king must be chosen over one by a
+    /// checker, then the one taking the most kings, then the one taking
+    /// them earliest.
+    pub king_capture_priority: bool,
+    pub promotion: Promotion,
+}
+
+impl RulesConfig {
+    /// English checkers, also called American checkers.
+    pub const ENGLISH: RulesConfig = RulesConfig {
+        flying_kings: false,
+        men_capture_backwards: false,
+        men_capture_kings: true,
+        majority_capture: false,
+        king_capture_priority: false,
+        promotion: Promotion::EndsCapture,
+    };
+
+    /// Russian draughts: flying kings, and a checker crowned during a
+    /// capture goes on as a king.
+    pub const RUSSIAN: RulesConfig = RulesConfig {
+        flying_kings: true,
+        men_capture_backwards: true,
+        men_capture_kings: true,
+        majority_capture: false,
+        king_capture_priority: false,
+        promotion: Promotion::ContinuesAsKing,
+    };
+
+    /// Brazilian draughts, the international rules on an 8x8 board: the
+    /// capture taking the most pieces is mandatory.
+    pub const BRAZILIAN: RulesConfig = RulesConfig {
+        flying_kings: true,
+        men_capture_backwards: true,
+        men_capture_kings: true,
+        majority_capture: true,
+        king_capture_priority: false,
+        promotion: Promotion::AtEnd,
+    };
+
+    /// American pool checkers: like Brazilian, but any capture may be
+    /// chosen.
+    pub const POOL: RulesConfig = RulesConfig {
+        flying_kings: true,
+        men_capture_backwards: true,
+        men_capture_kings: true,
+        majority_capture: false,
+        king_capture_priority: false,
+        promotion: Promotion::AtEnd,
+    };
+
+    /// Italian draughts: short kings that checkers cannot capture, and the
+    /// majority and king priority rules.
+    pub const ITALIAN: RulesConfig = RulesConfig {
+        flying_kings: false,
+        men_capture_backwards: false,
+        men_capture_kings: false,
+        majority_capture: true,
+        king_capture_priority: true,
+        promotion: Promotion::EndsCapture,
+    };
+}
+
+impl Default for RulesConfig {
+    fn default() -> Self {
+        RulesConfig::ENGLISH
+    }
+}
+
+/// The square of an opponent's piece that can be captured and the squares
+/// the capturing piece can land on behind it.
+struct Jump {
+    target: (usize, usize),
+    landings: Vec<(usize, usize)>,
+}
+
+/// What `make_move` changed, for `unmake_move` to put back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckersUndo {
+    /// Start and end square of the moved piece and the piece before any
+    /// promotion, `None` if the action was empty.
+    moved: Option<(usize, usize, usize, usize, CheckersTile)>,
+    /// Captured pieces and their squares.
+    captured: Vec<(usize, usize, CheckersTile)>,
+    moves_without_capture: u32,
+}
+
+/// The rule that ended a drawn game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawRule {
+    /// The side to move has no legal move.
+    NoMoves,
+    /// 50 moves in a row without a capture.
+    MoveLimit,
+    /// The same position occurred for the third time.
+    Repetition,
+}
+
+#[derive(Clone)]
 pub struct CheckersBoard {
-    pub board: [[CheckersTile; 8]; 8],
-    pub current_player: Player,
+    board: [[CheckersTile; 8]; 8],
+    current_player: Player,
     pub moves_without_capture: u32,
+    /// Zobrist key of `board` and `current_player`, see `GameState::hash_key`.
+    /// The move counter is not part of it. `board` and `current_player` are
+    /// only changed through `set_tile` and moves so that it stays up to date.
+    key: u64,
+    /// Keys of the positions before each move made on this board, for
+    /// repetitions. Not part of the position when comparing boards.
+    history: Vec<u64>,
+    rules: RulesConfig,
+}
+
+impl PartialEq for CheckersBoard {
+    fn eq(&self, other: &Self) -> bool {
+        self.board == other.board
+            && self.current_player == other.current_player
+            && self.moves_without_capture == other.moves_without_capture
+            && self.rules == other.rules
+    }
+}
+
+impl Eq for CheckersBoard {}
+
+impl Hash for CheckersBoard {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.board.hash(state);
+        self.current_player.hash(state);
+        self.moves_without_capture.hash(state);
+        self.rules.hash(state);
+    }
 }
 
 impl Debug for CheckersBoard {
@@ -54,237 +234,428 @@ impl Debug for CheckersBoard {
     }
 }
 
+impl Default for CheckersBoard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl CheckersBoard {
     pub fn new() -> Self {
         let mut board = [[CheckersTile::Empty; 8]; 8];
-        for row in 0..8 {
+        for (row, tiles) in board.iter_mut().enumerate() {
             for col in ((row % 2)..8).step_by(2) {
                 if row < 3 {
-                    board[row][col] = CheckersTile::Checker(Player::Player1);
+                    tiles[col] = CheckersTile::Checker(Player::Player1);
                 } else if row > 4 {
-                    board[row][col] = CheckersTile::Checker(Player::Player2);
+                    tiles[col] = CheckersTile::Checker(Player::Player2);
                 }
             }
         }
-        CheckersBoard { board, current_player: Player::Player1, moves_without_capture: 0 }
+        CheckersBoard::from_board(board, Player::Player1, 0)
+    }
+
+    /// Position with the given pieces and side to move.
+    pub fn from_board(
+        board: [[CheckersTile; 8]; 8],
+        current_player: Player,
+        moves_without_capture: u32,
+    ) -> Self {
+        let mut key = if current_player == Player::Player2 {
+            PLAYER2_KEY
+        } else {
+            0
+        };
+        for (row, tiles) in board.iter().enumerate() {
+            for (col, &tile) in tiles.iter().enumerate() {
+                key ^= piece_key(row, col, tile);
+            }
+        }
+        CheckersBoard {
+            board,
+            current_player,
+            moves_without_capture,
+            key,
+            history: Vec::new(),
+            rules: RulesConfig::ENGLISH,
+        }
+    }
+
+    /// The same position played by other rules.
+    pub fn with_rules(mut self, rules: RulesConfig) -> Self {
+        self.rules = rules;
+        self
     }
 
-    fn is_valid_position(&self, row: usize, col: usize) -> bool {
-        row < 8 && col < 8
+    pub fn rules(&self) -> RulesConfig {
+        self.rules
     }
 
-    fn get_piece_at(&self, row: usize, col: usize) -> CheckersTile {
-        if self.is_valid_position(row, col) {
-            self.board[row][col]
+    /// Whether this position, with the same side to move, already occurred
+    /// twice since the last capture. Only moves made on this board count, a
+    /// board from `from_board` has no history.
+    pub fn is_threefold_repetition(&self) -> bool {
+        let since_capture = self
+            .history
+            .len()
+            .saturating_sub(self.moves_without_capture as usize);
+        self.history[since_capture..]
+            .iter()
+            .filter(|&&key| key == self.key)
+            .count()
+            >= 2
+    }
+
+    /// The rule that ended the game in a draw, `None` if it goes on or was
+    /// won.
+    pub fn draw_rule(&self) -> Option<DrawRule> {
+        if self.count_pieces(Player::Player1) == 0 || self.count_pieces(Player::Player2) == 0 {
+            None
+        } else if self.is_threefold_repetition() {
+            Some(DrawRule::Repetition)
+        } else if self.moves_without_capture >= 50 {
+            Some(DrawRule::MoveLimit)
+        } else if !self.has_any_moves(self.current_player) {
+            Some(DrawRule::NoMoves)
         } else {
-            CheckersTile::Empty
+            None
         }
     }
 
-    fn get_moves_for_piece(&self, row: usize, col: usize) -> Vec<Vec<(usize, usize, usize, usize)>> {
-        let mut moves = Vec::new();
-        let piece = self.get_piece_at(row, col);
-        
-        match piece {
-            CheckersTile::Checker(player) | CheckersTile::Queen(player) => {
-                if player != self.current_player {
-                    return moves;
+    pub fn board(&self) -> &[[CheckersTile; 8]; 8] {
+        &self.board
+    }
+
+    pub fn current_player(&self) -> Player {
+        self.current_player
+    }
+
+    /// Puts `tile` on a square, keeping the key up to date.
+    pub fn set_tile(&mut self, row: usize, col: usize, tile: CheckersTile) {
+        self.key ^= piece_key(row, col, self.board[row][col]) ^ piece_key(row, col, tile);
+        self.board[row][col] = tile;
+    }
+
+    /// The square one step along a diagonal, `None` off the board.
+    fn step(row: usize, col: usize, dr: i32, dc: i32) -> Option<(usize, usize)> {
+        let row = row.checked_add_signed(dr as isize).filter(|&row| row < 8)?;
+        let col = col.checked_add_signed(dc as isize).filter(|&col| col < 8)?;
+        Some((row, col))
+    }
+
+    /// Row direction in which `player`'s checkers move.
+    fn forward(player: Player) -> i32 {
+        if player == Player::Player1 { 1 } else { -1 }
+    }
+
+    /// Row on which `player`'s checkers are crowned.
+    pub fn crowning_row(player: Player) -> usize {
+        if player == Player::Player1 { 7 } else { 0 }
+    }
+
+    /// The opponent's piece a step jumps over, `None` for a plain move.
+    fn captured_square(&self, step: (usize, usize, usize, usize)) -> Option<(usize, usize)> {
+        let (from_row, from_col, to_row, to_col) = step;
+        let dr = if to_row > from_row { 1 } else { -1 };
+        let dc = if to_col > from_col { 1 } else { -1 };
+        let mut square = Self::step(from_row, from_col, dr, dc);
+        while let Some((row, col)) = square
+            && (row, col) != (to_row, to_col)
+        {
+            match self.board[row][col] {
+                CheckersTile::Checker(player) | CheckersTile::Queen(player)
+                    if player != self.current_player =>
+                {
+                    return Some((row, col));
                 }
-                
+                _ => square = Self::step(row, col, dr, dc),
+            }
+        }
+        None
+    }
+
+    /// Whether `action` captures. Captures are mandatory, so in a position
+    /// either every action captures or none does.
+    pub fn is_capture(&self, action: &[(usize, usize, usize, usize)]) -> bool {
+        action
+            .first()
+            .is_some_and(|&step| self.captured_square(step).is_some())
+    }
+
+    fn get_moves_for_piece(
+        &self,
+        row: usize,
+        col: usize,
+    ) -> Vec<Vec<(usize, usize, usize, usize)>> {
+        match self.board[row][col] {
+            CheckersTile::Checker(player) | CheckersTile::Queen(player)
+                if player == self.current_player =>
+            {
                 // Get capture moves first (mandatory)
                 let capture_moves = self.get_capture_moves_for_piece(row, col);
                 if !capture_moves.is_empty() {
                     return capture_moves;
                 }
-                
+
                 // If no captures, get regular moves
-                let regular_moves = self.get_regular_moves_for_piece(row, col);
-                for mov in regular_moves {
-                    moves.push(vec![mov]); // Single move as a sequence
-                }
+                self.get_regular_moves_for_piece(row, col)
+                    .into_iter()
+                    .map(|mov| vec![mov]) // Single move as a sequence
+                    .collect()
             }
-            _ => {}
+            _ => Vec::new(),
         }
-        
-        moves
     }
 
-    fn get_regular_moves_for_piece(&self, row: usize, col: usize) -> Vec<(usize, usize, usize, usize)> {
+    fn get_regular_moves_for_piece(
+        &self,
+        row: usize,
+        col: usize,
+    ) -> Vec<(usize, usize, usize, usize)> {
         let mut moves = Vec::new();
-        let piece = self.get_piece_at(row, col);
-        
-        match piece {
-            CheckersTile::Checker(player) => {
-                let directions = if player == Player::Player1 {
-                    vec![(1, -1), (1, 1)] // Forward diagonals for Player1
+        let (forward, flying) = match self.board[row][col] {
+            // Forward diagonals only
+            CheckersTile::Checker(player) => (Some(Self::forward(player)), false),
+            // Queens can move in all diagonal directions
+            CheckersTile::Queen(_) => (None, self.rules.flying_kings),
+            CheckersTile::Empty => return moves,
+        };
+
+        for (dr, dc) in DIAGONALS {
+            if forward.is_some_and(|forward| forward != dr) {
+                continue;
+            }
+            let mut square = Self::step(row, col, dr, dc);
+            while let Some((new_row, new_col)) = square
+                && self.board[new_row][new_col] == CheckersTile::Empty
+            {
+                moves.push((row, col, new_row, new_col));
+                square = if flying {
+                    Self::step(new_row, new_col, dr, dc)
                 } else {
-                    vec![(-1, -1), (-1, 1)] // Forward diagonals for Player2
+                    None
                 };
-                
-                for (dr, dc) in directions {
-                    let new_row = row as i32 + dr;
-                    let new_col = col as i32 + dc;
-                    
-                    if new_row >= 0 && new_row < 8 && new_col >= 0 && new_col < 8 {
-                        let new_row = new_row as usize;
-                        let new_col = new_col as usize;
-                        
-                        if self.get_piece_at(new_row, new_col) == CheckersTile::Empty {
-                            moves.push((row, col, new_row, new_col));
-                        }
-                    }
-                }
             }
-            CheckersTile::Queen(_) => {
-                // Queens can move in all diagonal directions
-                let directions = vec![(-1, -1), (-1, 1), (1, -1), (1, 1)];
-                
-                for (dr, dc) in directions {
-                    let new_row = row as i32 + dr;
-                    let new_col = col as i32 + dc;
-                    
-                    if new_row >= 0 && new_row < 8 && new_col >= 0 && new_col < 8 {
-                        let new_row_u = new_row as usize;
-                        let new_col_u = new_col as usize;
-                        
-                        if self.get_piece_at(new_row_u, new_col_u) == CheckersTile::Empty {
-                            moves.push((row, col, new_row_u, new_col_u));
-                        }
-                    }
-                }
-            }
-            _ => {}
         }
-        
+
         moves
     }
 
-    fn get_capture_moves_for_piece(&self, row: usize, col: usize) -> Vec<Vec<(usize, usize, usize, usize)>> {
+    fn get_capture_moves_for_piece(
+        &self,
+        row: usize,
+        col: usize,
+    ) -> Vec<Vec<(usize, usize, usize, usize)>> {
         let mut all_capture_sequences = Vec::new();
-        self.find_all_capture_sequences(row, col, vec![], &mut all_capture_sequences);
+        // Only pieces with a first jump need the scratch board the sequences are played out on
+        if self.find_jumps(row, col, 0).is_empty() {
+            return all_capture_sequences;
+        }
+        let mut scratch = CheckersBoard {
+            history: Vec::new(),
+            ..*self
+        };
+        scratch.find_all_capture_sequences(row, col, 0, vec![], &mut all_capture_sequences);
         all_capture_sequences
     }
 
-    fn find_all_capture_sequences(&self, row: usize, col: usize, current_sequence: Vec<(usize, usize, usize, usize)>, all_sequences: &mut Vec<Vec<(usize, usize, usize, usize)>>) {
-        let piece = self.get_piece_at(row, col);
-        let mut found_capture = false;
-        
-        match piece {
-            CheckersTile::Checker(player) => {
-                let directions = if player == Player::Player1 {
-                    vec![(1, -1), (1, 1)] // Forward diagonals for Player1
+    /// Jumps open to the piece on (row, col). Pieces in `captured`, a bit
+    /// per square, were already jumped in this move. They stay on the board
+    /// until it ends, so they block the way but cannot be captured twice.
+    fn find_jumps(&self, row: usize, col: usize, captured: u64) -> Vec<Jump> {
+        let (player, king) = match self.board[row][col] {
+            CheckersTile::Checker(player) => (player, false),
+            CheckersTile::Queen(player) => (player, true),
+            CheckersTile::Empty => return Vec::new(),
+        };
+        let flying = king && self.rules.flying_kings;
+        let mut jumps = Vec::new();
+
+        for (dr, dc) in DIAGONALS {
+            if !king && !self.rules.men_capture_backwards && dr != Self::forward(player) {
+                continue;
+            }
+
+            // A flying king may jump a piece from any distance
+            let mut target = Self::step(row, col, dr, dc);
+            while flying
+                && let Some((target_row, target_col)) = target
+                && self.board[target_row][target_col] == CheckersTile::Empty
+            {
+                target = Self::step(target_row, target_col, dr, dc);
+            }
+            let Some((target_row, target_col)) = target else {
+                continue;
+            };
+            let capturable = match self.board[target_row][target_col] {
+                CheckersTile::Checker(owner) => owner != player,
+                CheckersTile::Queen(owner) => {
+                    owner != player && (king || self.rules.men_capture_kings)
+                }
+                CheckersTile::Empty => false,
+            };
+            if !capturable || captured & square_bit(target_row, target_col) != 0 {
+                continue;
+            }
+
+            // ... and land on any empty square behind it
+            let mut landings = Vec::new();
+            let mut landing = Self::step(target_row, target_col, dr, dc);
+            while let Some((landing_row, landing_col)) = landing
+                && self.board[landing_row][landing_col] == CheckersTile::Empty
+            {
+                landings.push((landing_row, landing_col));
+                landing = if flying {
+                    Self::step(landing_row, landing_col, dr, dc)
                 } else {
-                    vec![(-1, -1), (-1, 1)] // Forward diagonals for Player2
+                    None
                 };
-                
-                for (dr, dc) in directions {
-                    if let Some(capture_move) = self.find_single_capture(row, col, dr, dc) {
-                        found_capture = true;
-                        let (_, _, to_row, to_col) = capture_move;
-                        
-                        // Create a new sequence with this capture
-                        let mut new_sequence = current_sequence.clone();
-                        new_sequence.push(capture_move);
-                        
-                        // Simulate the board state after this capture
-                        let temp_board = self.apply_capture_sequence(&new_sequence);
-                        
-                        // Look for more captures from the new position
-                        temp_board.find_all_capture_sequences(to_row, to_col, new_sequence, all_sequences);
-                    }
-                }
             }
-            CheckersTile::Queen(_) => {
-                let directions = vec![(-1, -1), (-1, 1), (1, -1), (1, 1)];
-                
-                for (dr, dc) in directions {
-                    if let Some(capture_move) = self.find_single_capture(row, col, dr, dc) {
-                        found_capture = true;
-                        let (_, _, to_row, to_col) = capture_move;
-                        
-                        // Create a new sequence with this capture
-                        let mut new_sequence = current_sequence.clone();
-                        new_sequence.push(capture_move);
-                        
-                        // Simulate the board state after this capture
-                        let temp_board = self.apply_capture_sequence(&new_sequence);
-                        
-                        // Look for more captures from the new position
-                        temp_board.find_all_capture_sequences(to_row, to_col, new_sequence, all_sequences);
-                    }
-                }
+            if !landings.is_empty() {
+                jumps.push(Jump {
+                    target: (target_row, target_col),
+                    landings,
+                });
             }
-            _ => {}
         }
-        
+
+        jumps
+    }
+
+    /// Plays each jump on this board, searches on from there and takes the
+    /// jump back, so the board is unchanged when this returns. The key is not
+    /// kept up to date meanwhile.
+    fn find_all_capture_sequences(
+        &mut self,
+        row: usize,
+        col: usize,
+        captured: u64,
+        current_sequence: Vec<(usize, usize, usize, usize)>,
+        all_sequences: &mut Vec<Vec<(usize, usize, usize, usize)>>,
+    ) {
+        let jumps = self.find_jumps(row, col, captured);
+
         // If we found no more captures and we have a sequence, add it to results
-        if !found_capture && !current_sequence.is_empty() {
-            all_sequences.push(current_sequence);
-        }
-    }
-
-    fn find_single_capture(&self, row: usize, col: usize, dr: i32, dc: i32) -> Option<(usize, usize, usize, usize)> {
-        let opponent_row = row as i32 + dr;
-        let opponent_col = col as i32 + dc;
-        
-        // Check if opponent position is valid
-        if opponent_row < 0 || opponent_row >= 8 || opponent_col < 0 || opponent_col >= 8 {
-            return None;
-        }
-        
-        let opponent_row = opponent_row as usize;
-        let opponent_col = opponent_col as usize;
-        
-        // Check if there's an opponent piece at this position
-        match self.get_piece_at(opponent_row, opponent_col) {
-            CheckersTile::Checker(player) | CheckersTile::Queen(player) if player != self.current_player => {
-                // Found opponent piece, check landing position
-                let landing_row = opponent_row as i32 + dr;
-                let landing_col = opponent_col as i32 + dc;
-                
-                if landing_row >= 0 && landing_row < 8 && landing_col >= 0 && landing_col < 8 {
-                    let landing_row = landing_row as usize;
-                    let landing_col = landing_col as usize;
-                    
-                    // Check if landing position is empty
-                    if self.get_piece_at(landing_row, landing_col) == CheckersTile::Empty {
-                        return Some((row, col, landing_row, landing_col));
-                    }
+        if jumps.is_empty() {
+            if !current_sequence.is_empty() {
+                all_sequences.push(current_sequence);
+            }
+            return;
+        }
+
+        for Jump { target, landings } in jumps {
+            let captured = captured | square_bit(target.0, target.1);
+            // A capture cannot stop while it can go on, so landing squares
+            // that end it only count if none of them lets it continue
+            let mut endings = Vec::new();
+            let mut continued = false;
+
+            for (to_row, to_col) in landings {
+                // Create a new sequence with this capture
+                let mut new_sequence = current_sequence.clone();
+                new_sequence.push((row, col, to_row, to_col));
+
+                let piece = self.jump(row, col, to_row, to_col);
+                // A checker crowned by this jump may end its move there
+                let crowned = match piece {
+                    CheckersTile::Checker(player) => to_row == Self::crowning_row(player),
+                    _ => false,
+                };
+                if (crowned && self.rules.promotion == Promotion::EndsCapture)
+                    || self.find_jumps(to_row, to_col, captured).is_empty()
+                {
+                    endings.push(new_sequence);
+                } else {
+                    // Look for more captures from the new position
+                    continued = true;
+                    self.find_all_capture_sequences(
+                        to_row,
+                        to_col,
+                        captured,
+                        new_sequence,
+                        all_sequences,
+                    );
                 }
+                self.take_back_jump(row, col, to_row, to_col, piece);
+            }
+
+            if !continued {
+                all_sequences.append(&mut endings);
             }
-            _ => {}
         }
-        
-        None
     }
 
-    fn apply_capture_sequence(&self, sequence: &[(usize, usize, usize, usize)]) -> CheckersBoard {
-        let mut board = self.clone();
-        
-        for &(from_row, from_col, to_row, to_col) in sequence {
-            // Move the piece
-            let piece = board.board[from_row][from_col];
-            board.board[to_row][to_col] = piece;
-            board.board[from_row][from_col] = CheckersTile::Empty;
-            
-            // Remove captured piece (always exactly one square diagonally between from and to)
-            let captured_row = (from_row + to_row) / 2;
-            let captured_col = (from_col + to_col) / 2;
-            board.board[captured_row][captured_col] = CheckersTile::Empty;
-            
-            // Check for promotion
-            match piece {
-                CheckersTile::Checker(Player::Player1) if to_row == 7 => {
-                    board.board[to_row][to_col] = CheckersTile::Queen(Player::Player1);
-                }
-                CheckersTile::Checker(Player::Player2) if to_row == 0 => {
-                    board.board[to_row][to_col] = CheckersTile::Queen(Player::Player2);
-                }
-                _ => {}
+    /// Moves the piece for one jump of a capture, crowning it on the way if
+    /// it goes on as a king. Returns the piece as it was.
+    fn jump(
+        &mut self,
+        from_row: usize,
+        from_col: usize,
+        to_row: usize,
+        to_col: usize,
+    ) -> CheckersTile {
+        let piece = self.board[from_row][from_col];
+        self.board[from_row][from_col] = CheckersTile::Empty;
+        self.board[to_row][to_col] = match piece {
+            CheckersTile::Checker(player)
+                if to_row == Self::crowning_row(player)
+                    && self.rules.promotion == Promotion::ContinuesAsKing =>
+            {
+                CheckersTile::Queen(player)
             }
+            _ => piece,
+        };
+        piece
+    }
+
+    fn take_back_jump(
+        &mut self,
+        from_row: usize,
+        from_col: usize,
+        to_row: usize,
+        to_col: usize,
+        piece: CheckersTile,
+    ) {
+        self.board[to_row][to_col] = CheckersTile::Empty;
+        self.board[from_row][from_col] = piece;
+    }
+
+    /// Narrows mandatory captures down to the ones the rules allow: the
+    /// longest under the majority rule, then those the king priority picks.
+    fn restrict_captures(
+        &self,
+        mut captures: Vec<Vec<(usize, usize, usize, usize)>>,
+    ) -> Vec<Vec<(usize, usize, usize, usize)>> {
+        if self.rules.majority_capture {
+            let most = captures.iter().map(Vec::len).max().unwrap_or(0);
+            captures.retain(|action| action.len() == most);
         }
-        
-        board
+        if self.rules.king_capture_priority {
+            // A king captures before a checker does, then the capture taking
+            // the most kings, then the one taking them earliest
+            let by_king = |action: &Vec<(usize, usize, usize, usize)>| {
+                let (row, col, _, _) = action[0];
+                matches!(self.board[row][col], CheckersTile::Queen(_))
+            };
+            if captures.iter().any(by_king) {
+                captures.retain(by_king);
+            }
+            let kings_taken = |action: &Vec<(usize, usize, usize, usize)>| {
+                let kings: Vec<bool> = action
+                    .iter()
+                    .map(|&step| {
+                        self.captured_square(step).is_some_and(|(row, col)| {
+                            matches!(self.board[row][col], CheckersTile::Queen(_))
+                        })
+                    })
+                    .collect();
+                (kings.iter().filter(|&&king| king).count(), kings)
+            };
+            if let Some(best) = captures.iter().map(kings_taken).max() {
+                captures.retain(|action| kings_taken(action) == best);
+            }
+        }
+        captures
     }
 
     pub fn count_pieces(&self, player: Player) -> u32 {
@@ -304,10 +675,10 @@ impl CheckersBoard {
         for row in 0..8 {
             for col in 0..8 {
                 match self.board[row][col] {
-                    CheckersTile::Checker(p) | CheckersTile::Queen(p) if p == player => {
-                        if !self.get_moves_for_piece(row, col).is_empty() {
-                            return true;
-                        }
+                    CheckersTile::Checker(p) | CheckersTile::Queen(p)
+                        if p == player && !self.get_moves_for_piece(row, col).is_empty() =>
+                    {
+                        return true;
                     }
                     _ => {}
                 }
@@ -316,20 +687,25 @@ impl CheckersBoard {
         false
     }
 
-    fn calculate_proximity_bonus(&self, player1_pieces: &[(usize, usize)], player2_pieces: &[(usize, usize)]) -> f32 {
+    fn calculate_proximity_bonus(
+        &self,
+        player1_pieces: &[(usize, usize)],
+        player2_pieces: &[(usize, usize)],
+    ) -> f32 {
         let mut total_proximity = 0.0;
         let mut count = 0;
-        
+
         // Calculate average distance between pieces of different players
         for &(r1, c1) in player1_pieces {
             for &(r2, c2) in player2_pieces {
-                let distance = ((r1 as i32 - r2 as i32).abs() + (c1 as i32 - c2 as i32).abs()) as f32;
+                let distance =
+                    ((r1 as i32 - r2 as i32).abs() + (c1 as i32 - c2 as i32).abs()) as f32;
                 // Closer pieces get higher bonus (inverse of distance)
-                total_proximity += 1.0 - (distance*distance)/ (14.0*14.0); // Normalize to a range of 0 to 1
+                total_proximity += 1.0 - (distance * distance) / (14.0 * 14.0); // Normalize to a range of 0 to 1
                 count += 1;
             }
         }
-        
+
         if count > 0 {
             total_proximity / count as f32
         } else {
@@ -344,110 +720,74 @@ impl GameState for CheckersBoard {
     fn get_possible_actions(&self) -> Vec<Self::Action> {
         let mut all_moves = Vec::new();
         let mut capture_moves = Vec::new();
-        
+
         // Check all pieces for the current player
         for row in 0..8 {
             for col in 0..8 {
                 match self.board[row][col] {
-                    CheckersTile::Checker(player) | CheckersTile::Queen(player) if player == self.current_player => {
-                        let moves = self.get_moves_for_piece(row, col);
-                        for mov in moves {
-                            // Check if this is a capture sequence
-                            if mov.len() > 1 || (mov.len() == 1 && {
-                                let (from_row, from_col, to_row, to_col) = mov[0];
-                                let row_diff = (to_row as i32 - from_row as i32).abs();
-                                let col_diff = (to_col as i32 - from_col as i32).abs();
-                                row_diff == 2 && col_diff == 2 // Capture move
-                            }) {
-                                capture_moves.push(mov);
-                            } else {
-                                all_moves.push(mov);
-                            }
+                    CheckersTile::Checker(player) | CheckersTile::Queen(player)
+                        if player == self.current_player =>
+                    {
+                        let captures = self.get_capture_moves_for_piece(row, col);
+                        if !captures.is_empty() {
+                            capture_moves.extend(captures);
+                        } else if capture_moves.is_empty() {
+                            let moves = self.get_regular_moves_for_piece(row, col);
+                            all_moves.extend(moves.into_iter().map(|mov| vec![mov]));
                         }
                     }
                     _ => {}
                 }
             }
         }
-        
+
         // If there are capture moves, they are mandatory
         if !capture_moves.is_empty() {
-            capture_moves
+            self.restrict_captures(capture_moves)
         } else {
             all_moves
         }
     }
 
     fn apply_action(&self, action: &Self::Action) -> Self {
-        if action.is_empty() {
-            return self.clone();
-        }
-        
         let mut new_board = self.clone();
-        let mut captured_any = false;
-        
-        for &(from_row, from_col, to_row, to_col) in action {
-            // Move the piece
-            let piece = new_board.board[from_row][from_col];
-            new_board.board[to_row][to_col] = piece;
-            new_board.board[from_row][from_col] = CheckersTile::Empty;
-            
-            // Check if this is a capture move
-            let row_diff = (to_row as i32 - from_row as i32).abs();
-            let col_diff = (to_col as i32 - from_col as i32).abs();
-            
-            if row_diff == 2 && col_diff == 2 {
-                // This is a capture move - remove the captured piece
-                let captured_row = (from_row + to_row) / 2;
-                let captured_col = (from_col + to_col) / 2;
-                new_board.board[captured_row][captured_col] = CheckersTile::Empty;
-                captured_any = true;
-            }
-            
-            // Check for promotion
-            match piece {
-                CheckersTile::Checker(Player::Player1) if to_row == 7 => {
-                    new_board.board[to_row][to_col] = CheckersTile::Queen(Player::Player1);
-                }
-                CheckersTile::Checker(Player::Player2) if to_row == 0 => {
-                    new_board.board[to_row][to_col] = CheckersTile::Queen(Player::Player2);
-                }
-                _ => {}
-            }
-        }
-        
-        // Update move counter
-        if captured_any {
-            new_board.moves_without_capture = 0;
-        } else {
-            new_board.moves_without_capture += 1;
-        }
-        
-        new_board.current_player = new_board.current_player.switch();
+        new_board.make_move(action);
         new_board
     }
 
+    fn hash_key(&self) -> u64 {
+        self.key
+    }
+
+    fn is_path_dependent(&self) -> bool {
+        matches!(
+            self.draw_rule(),
+            Some(DrawRule::MoveLimit | DrawRule::Repetition)
+        )
+    }
+
     fn is_terminal(&self) -> bool {
         // Game is over if:
         // 1. One player has no pieces left
         // 2. No captures for more than 50 moves
-        // 3. Current player has no moves
-        
+        // 3. The position occurred for the third time
+        // 4. Current player has no moves
+
         let player1_pieces = self.count_pieces(Player::Player1);
         let player2_pieces = self.count_pieces(Player::Player2);
-        
+
         if player1_pieces == 0 || player2_pieces == 0 {
             return true;
         }
-        
-        if self.moves_without_capture >= 50 {
+
+        if self.moves_without_capture >= 50 || self.is_threefold_repetition() {
             return true;
         }
-        
+
         if !self.has_any_moves(self.current_player) {
             return true;
         }
-        
+
         false
     }
 
@@ -455,23 +795,21 @@ impl GameState for CheckersBoard {
         if self.is_terminal() {
             let player1_pieces = self.count_pieces(Player::Player1);
             let player2_pieces = self.count_pieces(Player::Player2);
-            
+
             if player1_pieces == 0 {
                 return Some(-1000.0); // Player 2 wins
             }
             if player2_pieces == 0 {
                 return Some(1000.0); // Player 1 wins
             }
-            if self.moves_without_capture >= 50 || !self.has_any_moves(self.current_player) {
-                return Some(0.0); // Draw
-            }
+            return Some(0.0); // Draw
         }
-        
+
         // Heuristic evaluation based on piece count and position
         let mut score = 0.0;
         let mut player1_pieces = Vec::new();
         let mut player2_pieces = Vec::new();
-        
+
         // First pass: calculate basic scores and collect piece positions
         for row in 0..8 {
             for col in 0..8 {
@@ -500,7 +838,7 @@ impl GameState for CheckersBoard {
                 }
             }
         }
-        
+
         // Add proximity bonus when one player has a material advantage
         let piece_count_diff = player1_pieces.len() as i32 - player2_pieces.len() as i32;
         if piece_count_diff.abs() > 2 {
@@ -513,9 +851,101 @@ impl GameState for CheckersBoard {
                 score -= proximity_bonus * 0.1;
             }
         }
-        
+
         Some(score)
     }
+
+    fn is_quiet(&self) -> bool {
+        // Captures are mandatory, so either every action is a capture or none is
+        !self
+            .get_possible_actions()
+            .first()
+            .is_some_and(|action| self.is_capture(action))
+    }
+}
+
+impl MakeMove for CheckersBoard {
+    type Undo = CheckersUndo;
+
+    fn make_move(&mut self, action: &Self::Action) -> CheckersUndo {
+        let mut undo = CheckersUndo {
+            moved: None,
+            captured: Vec::new(),
+            moves_without_capture: self.moves_without_capture,
+        };
+        let (Some(&(start_row, start_col, _, _)), Some(&(_, _, end_row, end_col))) =
+            (action.first(), action.last())
+        else {
+            return undo;
+        };
+        self.history.push(self.key);
+        undo.moved = Some((
+            start_row,
+            start_col,
+            end_row,
+            end_col,
+            self.board[start_row][start_col],
+        ));
+
+        for (index, &step) in action.iter().enumerate() {
+            let (from_row, from_col, to_row, to_col) = step;
+
+            // Check if this is a capture move - remove the captured piece
+            if let Some((captured_row, captured_col)) = self.captured_square(step) {
+                undo.captured.push((
+                    captured_row,
+                    captured_col,
+                    self.board[captured_row][captured_col],
+                ));
+                self.set_tile(captured_row, captured_col, CheckersTile::Empty);
+            }
+
+            // Move the piece
+            let piece = self.board[from_row][from_col];
+            self.set_tile(to_row, to_col, piece);
+            self.set_tile(from_row, from_col, CheckersTile::Empty);
+
+            // Check for promotion, which may wait for the end of the move
+            let last = index + 1 == action.len();
+            match piece {
+                CheckersTile::Checker(player)
+                    if to_row == Self::crowning_row(player)
+                        && (last || self.rules.promotion != Promotion::AtEnd) =>
+                {
+                    self.set_tile(to_row, to_col, CheckersTile::Queen(player));
+                }
+                _ => {}
+            }
+        }
+
+        // Update move counter
+        if undo.captured.is_empty() {
+            self.moves_without_capture += 1;
+        } else {
+            self.moves_without_capture = 0;
+        }
+
+        self.current_player = self.current_player.switch();
+        self.key ^= PLAYER2_KEY;
+        undo
+    }
+
+    fn unmake_move(&mut self, undo: CheckersUndo) {
+        let Some((start_row, start_col, end_row, end_col, piece)) = undo.moved else {
+            return;
+        };
+        self.current_player = self.current_player.switch();
+        self.key ^= PLAYER2_KEY;
+        self.moves_without_capture = undo.moves_without_capture;
+        self.history.pop();
+
+        // The end square is cleared first, a king's capture can end where it started
+        self.set_tile(end_row, end_col, CheckersTile::Empty);
+        self.set_tile(start_row, start_col, piece);
+        for (row, col, tile) in undo.captured {
+            self.set_tile(row, col, tile);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -527,11 +957,11 @@ mod tests {
         let board = CheckersBoard::new();
         assert_eq!(board.current_player, Player::Player1);
         assert_eq!(board.moves_without_capture, 0);
-        
+
         // Check that Player1 pieces are in the first 3 rows
         let mut player1_count = 0;
         let mut player2_count = 0;
-        
+
         for row in 0..8 {
             for col in 0..8 {
                 match board.board[row][col] {
@@ -541,7 +971,7 @@ mod tests {
                 }
             }
         }
-        
+
         assert_eq!(player1_count, 12);
         assert_eq!(player2_count, 12);
     }
@@ -550,10 +980,10 @@ mod tests {
     fn test_get_possible_actions() {
         let board = CheckersBoard::new();
         let actions = board.get_possible_actions();
-        
+
         // At the start, Player1 should have 7 possible moves (4 edge pieces can move)
         assert_eq!(actions.len(), 7);
-        
+
         // All initial moves should be forward diagonal moves
         for action in actions {
             assert_eq!(action.len(), 1); // Single move actions
@@ -567,7 +997,7 @@ mod tests {
     fn test_terminal_conditions() {
         let board = CheckersBoard::new();
         assert!(!board.is_terminal()); // Game should not be terminal at start
-        
+
         // Test 50 move rule
         let mut long_game = board.clone();
         long_game.moves_without_capture = 50;
@@ -577,26 +1007,26 @@ mod tests {
     #[test]
     fn test_game_flow() {
         let mut board = CheckersBoard::new();
-        
+
         // Get initial actions for Player1
         let actions = board.get_possible_actions();
         assert!(!actions.is_empty());
-        
+
         // Make a move
         let first_action = &actions[0];
         board = board.apply_action(first_action);
-        
+
         // Now it should be Player2's turn
         assert_eq!(board.current_player, Player::Player2);
-        
+
         // Player2 should also have moves
         let player2_actions = board.get_possible_actions();
         assert!(!player2_actions.is_empty());
-        
+
         // Test evaluation
         let evaluation = board.evaluate();
         assert!(evaluation.is_some());
-        
+
         // Should be close to 0 at the start (balanced)
         let eval_value = evaluation.unwrap();
         assert!(eval_value.abs() < 50.0); // Should be reasonably balanced
@@ -605,90 +1035,391 @@ mod tests {
     #[test]
     fn test_piece_promotion() {
         // Create a board with a piece near promotion
-        let mut board = CheckersBoard::new();
-        
+        let mut tiles = *CheckersBoard::new().board();
+
         // Manually set up a scenario where a piece can be promoted
-        board.board[6][1] = CheckersTile::Checker(Player::Player1);
-        board.board[7][2] = CheckersTile::Empty;
-        
+        tiles[6][1] = CheckersTile::Checker(Player::Player1);
+        tiles[7][2] = CheckersTile::Empty;
+        let board = CheckersBoard::from_board(tiles, Player::Player1, 0);
+
         // Apply a move that should promote the piece
         let action = vec![(6, 1, 7, 2)];
         let promoted_board = board.apply_action(&action);
-        
+
         // Check that the piece was promoted to a queen
-        assert_eq!(promoted_board.board[7][2], CheckersTile::Queen(Player::Player1));
+        assert_eq!(
+            promoted_board.board[7][2],
+            CheckersTile::Queen(Player::Player1)
+        );
     }
 
     #[test]
     fn test_multiple_captures() {
-        let mut board = CheckersBoard::new();
-        
+        let mut tiles = *CheckersBoard::new().board();
+
         // Set up a scenario with multiple captures possible
-        board.board[2][1] = CheckersTile::Checker(Player::Player1);
-        board.board[3][2] = CheckersTile::Checker(Player::Player2);
-        board.board[5][4] = CheckersTile::Checker(Player::Player2);
-        board.board[4][3] = CheckersTile::Empty;
-        board.board[6][5] = CheckersTile::Empty;
-        
+        tiles[2][1] = CheckersTile::Checker(Player::Player1);
+        tiles[3][2] = CheckersTile::Checker(Player::Player2);
+        tiles[5][4] = CheckersTile::Checker(Player::Player2);
+        tiles[4][3] = CheckersTile::Empty;
+        tiles[6][5] = CheckersTile::Empty;
+        let board = CheckersBoard::from_board(tiles, Player::Player1, 0);
+
         // Player1 should be able to capture both pieces in one move
         let actions = board.get_possible_actions();
-        
+
         // Look for a multi-capture sequence
         let multi_capture = actions.iter().find(|action| action.len() > 1);
-        assert!(multi_capture.is_some(), "Should find a multi-capture sequence");
-        
+        assert!(
+            multi_capture.is_some(),
+            "Should find a multi-capture sequence"
+        );
+
         if let Some(sequence) = multi_capture {
             // Apply the multi-capture
             let new_board = board.apply_action(sequence);
-            
+
             // Both opponent pieces should be removed
             assert_eq!(new_board.board[3][2], CheckersTile::Empty);
             assert_eq!(new_board.board[5][4], CheckersTile::Empty);
-            
+
             // The capturing piece should be at the final position
             let final_move = sequence.last().unwrap();
             let (_, _, final_row, final_col) = *final_move;
-            assert_eq!(new_board.board[final_row][final_col], CheckersTile::Checker(Player::Player1));
+            assert_eq!(
+                new_board.board[final_row][final_col],
+                CheckersTile::Checker(Player::Player1)
+            );
         }
     }
 
     #[test]
     fn test_single_capture() {
-        let mut board = CheckersBoard::new();
-        
-        // Set up a simple capture scenario
-        board.board[2][1] = CheckersTile::Checker(Player::Player1);
-        board.board[3][2] = CheckersTile::Checker(Player::Player2);
-        board.board[4][3] = CheckersTile::Empty;
-        
-        // Clear other pieces to avoid interference
-        for row in 0..8 {
-            for col in 0..8 {
-                if (row, col) != (2, 1) && (row, col) != (3, 2) {
-                    board.board[row][col] = CheckersTile::Empty;
-                }
-            }
-        }
-        
+        // Set up a simple capture scenario, with no other pieces to
+        // interfere
+        let mut tiles = [[CheckersTile::Empty; 8]; 8];
+        tiles[2][1] = CheckersTile::Checker(Player::Player1);
+        tiles[3][2] = CheckersTile::Checker(Player::Player2);
+        let board = CheckersBoard::from_board(tiles, Player::Player1, 0);
+
         let actions = board.get_possible_actions();
-        
+
         // Should have exactly one capture action
         assert_eq!(actions.len(), 1);
         let capture_action = &actions[0];
         assert_eq!(capture_action.len(), 1); // Single capture
-        
+
         let (from_row, from_col, to_row, to_col) = capture_action[0];
         assert_eq!(from_row, 2);
         assert_eq!(from_col, 1);
         assert_eq!(to_row, 4);
         assert_eq!(to_col, 3);
-        
+
         // Apply the capture
         let new_board = board.apply_action(capture_action);
-        
+
         // Check that the opponent piece was captured
         assert_eq!(new_board.board[3][2], CheckersTile::Empty);
-        assert_eq!(new_board.board[4][3], CheckersTile::Checker(Player::Player1));
+        assert_eq!(
+            new_board.board[4][3],
+            CheckersTile::Checker(Player::Player1)
+        );
         assert_eq!(new_board.board[2][1], CheckersTile::Empty);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_quiescence_sees_recapture() {
+        use crate::minmax::{TranspositionTable, minmax};
+
+        let mut tiles = [[CheckersTile::Empty; 8]; 8];
+        tiles[0][7] = CheckersTile::Queen(Player::Player1);
+        tiles[3][2] = CheckersTile::Checker(Player::Player1);
+        tiles[5][0] = CheckersTile::Checker(Player::Player2);
+        tiles[5][4] = CheckersTile::Checker(Player::Player2);
+        let board = CheckersBoard::from_board(tiles, Player::Player1, 0);
+
+        // Advancing the checker looks good statically but either square gets it captured
+        assert!(board.is_quiet());
+        let advanced = board.apply_action(&vec![(3, 2, 4, 3)]);
+        assert!(!advanced.is_quiet());
+
+        let mut table = TranspositionTable::new();
+        let result = minmax(
+            &board,
+            1,
+            true,
+            &mut table,
+            f32::NEG_INFINITY,
+            f32::INFINITY,
+        );
+        let safe_move = vec![(0, 7, 1, 6)];
+        assert_eq!(result.best_action, Some(safe_move.clone()));
+        assert_eq!(
+            result.evaluation,
+            board.apply_action(&safe_move).evaluate().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_crowning_ends_capture() {
+        let mut tiles = [[CheckersTile::Empty; 8]; 8];
+        tiles[2][5] = CheckersTile::Checker(Player::Player2);
+        tiles[1][4] = CheckersTile::Checker(Player::Player1);
+        tiles[1][2] = CheckersTile::Checker(Player::Player1);
+        let board = CheckersBoard::from_board(tiles, Player::Player2, 0);
+
+        // The new king could jump on over (1, 2), but crowning ends the move
+        assert_eq!(board.get_possible_actions(), vec![vec![(2, 5, 0, 3)]]);
+        let crowned = board.apply_action(&vec![(2, 5, 0, 3)]);
+        assert_eq!(crowned.board[0][3], CheckersTile::Queen(Player::Player2));
+    }
+
+    #[test]
+    fn test_hash_key_follows_moves() {
+        let mut state = CheckersBoard::new();
+        // An opening with an exchange of captures
+        for text in ["11-15", "23-19", "8-11", "22-18", "15x22", "25x18"] {
+            let action = crate::pdn::parse_move(&state, text, 1).unwrap();
+            state = state.apply_action(&action);
+            let rebuilt = CheckersBoard::from_board(
+                state.board,
+                state.current_player,
+                state.moves_without_capture,
+            );
+            assert_eq!(state.hash_key(), rebuilt.hash_key(), "after {}", text);
+        }
+
+        let mut board = [[CheckersTile::Empty; 8]; 8];
+        board[6][1] = CheckersTile::Checker(Player::Player1);
+        board[0][7] = CheckersTile::Checker(Player::Player2);
+        let state = CheckersBoard::from_board(board, Player::Player1, 0);
+        let crowned = state.apply_action(&vec![(6, 1, 7, 0)]);
+        assert_eq!(crowned.board[7][0], CheckersTile::Queen(Player::Player1));
+        board[6][1] = CheckersTile::Empty;
+        board[7][0] = CheckersTile::Queen(Player::Player1);
+        assert_eq!(
+            crowned.hash_key(),
+            CheckersBoard::from_board(board, Player::Player2, 0).hash_key()
+        );
+        assert_ne!(
+            crowned.hash_key(),
+            CheckersBoard::from_board(board, Player::Player1, 0).hash_key()
+        );
+
+        // Editing a square keeps the key up to date too
+        let mut edited = crowned.clone();
+        edited.set_tile(0, 7, CheckersTile::Empty);
+        board[0][7] = CheckersTile::Empty;
+        assert_eq!(
+            edited.hash_key(),
+            CheckersBoard::from_board(board, Player::Player2, 0).hash_key()
+        );
+    }
+
+    #[test]
+    fn test_make_and_unmake_move() {
+        let mut board = [[CheckersTile::Empty; 8]; 8];
+        board[4][3] = CheckersTile::Checker(Player::Player2);
+        board[3][2] = CheckersTile::Checker(Player::Player1);
+        board[1][2] = CheckersTile::Checker(Player::Player1);
+        board[0][7] = CheckersTile::Checker(Player::Player1);
+        let mut state = CheckersBoard::from_board(board, Player::Player2, 7);
+        let before = state.clone();
+
+        // A double jump ending on the back rank, then an ordinary reply
+        let capture = vec![(4, 3, 2, 1), (2, 1, 0, 3)];
+        assert!(state.get_possible_actions().contains(&capture));
+        let expected = state.apply_action(&capture);
+        let first = state.make_move(&capture);
+        assert_eq!(state, expected);
+        assert_eq!(state.hash_key(), expected.hash_key());
+        assert_eq!(state.board[0][3], CheckersTile::Queen(Player::Player2));
+        assert_eq!(state.moves_without_capture, 0);
+
+        let reply = state.get_possible_actions()[0].clone();
+        let expected = state.apply_action(&reply);
+        let second = state.make_move(&reply);
+        assert_eq!(state, expected);
+
+        state.unmake_move(second);
+        state.unmake_move(first);
+        assert_eq!(state, before);
+        assert_eq!(state.hash_key(), before.hash_key());
+        assert_eq!(state.history, before.history);
+    }
+
+    /// Two kings against one shuffling back and forth, with the side to move
+    /// after `plies` moves of the cycle.
+    fn shuffle_kings(plies: usize) -> CheckersBoard {
+        let mut board = [[CheckersTile::Empty; 8]; 8];
+        board[3][3] = CheckersTile::Queen(Player::Player1);
+        board[0][0] = CheckersTile::Queen(Player::Player1);
+        board[7][1] = CheckersTile::Queen(Player::Player2);
+        shuffle(CheckersBoard::from_board(board, Player::Player1, 0), plies)
+    }
+
+    /// Plays `plies` moves of the cycle of `shuffle_kings` from `state`.
+    fn shuffle(mut state: CheckersBoard, plies: usize) -> CheckersBoard {
+        let cycle = [(3, 3, 2, 2), (7, 1, 6, 0), (2, 2, 3, 3), (6, 0, 7, 1)];
+        for &step in cycle.iter().cycle().take(plies) {
+            state.make_move(&vec![step]);
+        }
+        state
+    }
+
+    #[test]
+    fn test_threefold_repetition() {
+        // The start position occurs for the second time, then the third
+        let twice = shuffle_kings(4);
+        assert!(!twice.is_terminal());
+        assert_eq!(twice.draw_rule(), None);
+
+        let three_times = shuffle_kings(8);
+        assert!(three_times.is_terminal());
+        assert_eq!(three_times.draw_rule(), Some(DrawRule::Repetition));
+        assert_eq!(three_times.evaluate(), Some(0.0));
+
+        // A capture makes the earlier positions unreachable
+        let mut captured = shuffle_kings(8);
+        captured.moves_without_capture = 0;
+        assert!(!captured.is_terminal());
+
+        let mut limit = CheckersBoard::new();
+        limit.moves_without_capture = 50;
+        assert_eq!(limit.draw_rule(), Some(DrawRule::MoveLimit));
+    }
+
+    #[test]
+    fn test_search_takes_repetition_draw() {
+        // Player2 is a king down and repeats the position a third time
+        let state = shuffle_kings(7);
+        let mut table = crate::minmax::TranspositionTable::new();
+        let result = crate::minmax::minmax_in_place(
+            &state,
+            2,
+            false,
+            &mut table,
+            f32::NEG_INFINITY,
+            f32::INFINITY,
+        );
+        assert_eq!(result.best_action, Some(vec![(6, 0, 7, 1)]));
+        assert_eq!(result.evaluation, 0.0);
+    }
+
+    #[test]
+    fn test_table_forgets_repetition_draw() {
+        // Player2 can draw by repetition, and advances its man instead once
+        // the earlier visits are forgotten
+        let state = shuffle(crate::pdn::parse_fen("B:WK32,26:BK4,K15").unwrap(), 7);
+        let search = |state: &CheckersBoard, table: &mut _| {
+            crate::minmax::minmax_in_place(state, 2, false, table, f32::NEG_INFINITY, f32::INFINITY)
+        };
+        let mut table = crate::minmax::TranspositionTable::new();
+        let drawn = search(&state, &mut table);
+        assert_eq!(drawn.best_action, Some(vec![(6, 0, 7, 1)]));
+        assert_eq!(drawn.evaluation, 0.0);
+
+        let fresh = CheckersBoard::from_board(state.board, Player::Player2, 0);
+        assert_eq!(fresh.hash_key(), state.hash_key());
+        let result = search(&fresh, &mut table);
+        assert_eq!(result.best_action, Some(vec![(6, 4, 5, 3)]));
+        assert!(result.evaluation > 0.0);
+    }
+
+    /// The legal moves in a position given in FEN under `rules`, in PDN
+    /// and sorted.
+    fn variant_moves(fen: &str, rules: RulesConfig) -> Vec<String> {
+        let state = crate::pdn::parse_fen(fen).unwrap().with_rules(rules);
+        let mut moves: Vec<_> = state
+            .get_possible_actions()
+            .iter()
+            .map(|action| crate::pdn::format_move(&state, action))
+            .collect();
+        moves.sort();
+        moves
+    }
+
+    #[test]
+    fn test_flying_kings() {
+        // The king has to land on 9, the only square it can capture on from
+        assert_eq!(
+            variant_moves("W:WK32:B6,18", RulesConfig::RUSSIAN),
+            ["32x9x2"]
+        );
+        assert_eq!(
+            variant_moves("W:WK32:B6,18", RulesConfig::ENGLISH),
+            ["32-27", "32-28"]
+        );
+
+        let moves = variant_moves("W:WK32:B1", RulesConfig::RUSSIAN);
+        assert_eq!(moves.len(), 7);
+        assert!(moves.contains(&"32-5".to_string()));
+    }
+
+    #[test]
+    fn test_men_capture_backwards() {
+        assert_eq!(variant_moves("B:W14:B18", RulesConfig::RUSSIAN), ["18x9"]);
+        assert_eq!(
+            variant_moves("B:W14:B18", RulesConfig::ENGLISH),
+            ["18-22", "18-23"]
+        );
+    }
+
+    #[test]
+    fn test_majority_capture() {
+        let fen = "B:W14,16,22:B9,12";
+        assert_eq!(variant_moves(fen, RulesConfig::POOL), ["12x19", "9x18x25"]);
+        assert_eq!(variant_moves(fen, RulesConfig::BRAZILIAN), ["9x18x25"]);
+    }
+
+    #[test]
+    fn test_promotion_during_capture() {
+        let fen = "B:W26,27:B22";
+        assert_eq!(variant_moves(fen, RulesConfig::ENGLISH), ["22x31"]);
+        assert_eq!(
+            variant_moves(fen, RulesConfig::RUSSIAN),
+            ["22x31x20", "22x31x24"]
+        );
+        assert_eq!(variant_moves(fen, RulesConfig::BRAZILIAN), ["22x31x24"]);
+
+        // Only the Russian checker is crowned on its way through
+        let (row, col) = crate::pdn::square_to_position(24).unwrap();
+        for (rules, tile) in [
+            (RulesConfig::RUSSIAN, CheckersTile::Queen(Player::Player1)),
+            (
+                RulesConfig::BRAZILIAN,
+                CheckersTile::Checker(Player::Player1),
+            ),
+        ] {
+            let state = crate::pdn::parse_fen(fen).unwrap().with_rules(rules);
+            let action = crate::pdn::parse_move(&state, "22x31x24", 1).unwrap();
+            assert_eq!(state.apply_action(&action).board[row][col], tile);
+        }
+    }
+
+    #[test]
+    fn test_italian_rules() {
+        // A king captures before a checker does
+        let fen = "W:W23,K29:B19,25";
+        assert_eq!(variant_moves(fen, RulesConfig::ENGLISH), ["23x16", "29x22"]);
+        assert_eq!(variant_moves(fen, RulesConfig::ITALIAN), ["29x22"]);
+
+        // Of two captures of three pieces, the one taking two kings
+        let fen = "W:WK29:B10,K11,K18,25";
+        assert_eq!(
+            variant_moves(fen, RulesConfig::ENGLISH),
+            ["29x22x15x6", "29x22x15x8"]
+        );
+        assert_eq!(variant_moves(fen, RulesConfig::ITALIAN), ["29x22x15x8"]);
+
+        // Checkers cannot capture kings
+        assert_eq!(
+            variant_moves("W:W22:B1,K18", RulesConfig::ENGLISH),
+            ["22x15"]
+        );
+        assert_eq!(
+            variant_moves("W:W22:B1,K18", RulesConfig::ITALIAN),
+            ["22-17"]
+        );
+    }
+}
@@ -1,25 +1,134 @@
-use std::fmt::Debug;
+use std::collections::HashSet;
+use std::fmt::{Debug, Display};
 
-use crate::minmax::{GameState, Player, Tile, GameResult};
+use crate::minmax::{GameResult, GameState, MakeMove, Player, Tile, zobrist_keys};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct GameAction {
     pub column: u8, // Column index where the disc is dropped
 }
 
+/// Size of the classic board.
 pub const BOARD_WIDTH: usize = 7;
 pub const BOARD_HEIGHT: usize = 6;
 
+/// Largest board `FourRow` can hold. Columns are written as single digits
+/// in move strings, so boards are at most 9 wide.
+pub const MAX_WIDTH: usize = 9;
+pub const MAX_HEIGHT: usize = 9;
+
+/// Score for a disc by its distance from the centre column.
+const CENTRE_SCORES: [f32; 4] = [0.005, 0.004, 0.002, 0.0005];
+
+/// Steps along a row, a column and both diagonals.
+const DIRECTIONS: [(i32, i32); 4] = [(0, 1), (1, 0), (1, 1), (-1, 1)];
+
+/// Zobrist keys for a disc of each player on every cell.
+const DISC_KEYS: [u64; MAX_HEIGHT * MAX_WIDTH * 2] = zobrist_keys(0x0046_4f55_5252_4f57);
+
+/// Part of the key while Player2 is to move.
+const PLAYER2_KEY: u64 = zobrist_keys::<1>(0x0050_4c41_5945_5232)[0];
+
+fn disc_key(row: usize, col: usize, player: Player) -> u64 {
+    let index = (row * MAX_WIDTH + col) * 2;
+    match player {
+        Player::Player1 => DISC_KEYS[index],
+        Player::Player2 => DISC_KEYS[index + 1],
+    }
+}
+
+/// Board geometry and the number of discs in a line needed to win.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BoardConfig {
+    pub width: usize,
+    pub height: usize,
+    pub win_length: usize,
+}
+
+impl BoardConfig {
+    /// The classic 7x6 game of connect four.
+    pub const CLASSIC: BoardConfig = BoardConfig {
+        width: BOARD_WIDTH,
+        height: BOARD_HEIGHT,
+        win_length: 4,
+    };
+
+    /// Returns `None` if the board is larger than `MAX_WIDTH` x `MAX_HEIGHT`
+    /// or a line of `win_length` fits in no direction.
+    pub fn new(width: usize, height: usize, win_length: usize) -> Option<Self> {
+        let valid = (1..=MAX_WIDTH).contains(&width)
+            && (1..=MAX_HEIGHT).contains(&height)
+            && win_length >= 3
+            && win_length <= width.max(height);
+        valid.then_some(BoardConfig {
+            width,
+            height,
+            win_length,
+        })
+    }
+}
+
+impl Default for BoardConfig {
+    fn default() -> Self {
+        BoardConfig::CLASSIC
+    }
+}
+
+/// Error when reading a move string. Positions are 0-based character
+/// indices into the string, columns are 1-based as in the notation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError {
+    /// The character is not a column number on this board.
+    InvalidColumn { position: usize, character: char },
+    /// The move drops a disc into a column that is already full.
+    ColumnFull { position: usize, column: u8 },
+    /// The move comes after a player already won or the board filled up.
+    GameOver { position: usize },
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::InvalidColumn {
+                position,
+                character,
+            } => write!(f, "invalid column '{}' at position {}", character, position),
+            ParseError::ColumnFull { position, column } => {
+                write!(
+                    f,
+                    "column {} is already full at position {}",
+                    column, position
+                )
+            }
+            ParseError::GameOver { position } => {
+                write!(
+                    f,
+                    "move at position {} comes after the game ended",
+                    position
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Connect four on a board of any size up to `MAX_WIDTH` x `MAX_HEIGHT`.
+/// Only the first `config.height` rows and `config.width` columns of
+/// `board` are used, the rest stays empty.
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub struct FourRow {
-    pub board: [[Tile; BOARD_WIDTH]; BOARD_HEIGHT],
+    pub board: [[Tile; MAX_WIDTH]; MAX_HEIGHT],
+    pub config: BoardConfig,
     pub current_player: Player,
+    /// Zobrist key of `board` and `current_player`, see `GameState::hash_key`.
+    key: u64,
 }
 
 impl Debug for FourRow {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for row in self.board.iter().rev() {
-            for tile in row {
+        for row in self.board[..self.config.height].iter().rev() {
+            for tile in &row[..self.config.width] {
                 let symbol = match tile {
                     Tile::Empty => ".",
                     Tile::Player1 => "X",
@@ -29,87 +138,149 @@ impl Debug for FourRow {
             }
             writeln!(f)?;
         }
-        for col in 0..BOARD_WIDTH {
+        // Columns are numbered from 1, as in move strings
+        for col in 1..=self.config.width {
             write!(f, "{} ", col)?;
         }
         Ok(())
     }
 }
 
+impl Default for FourRow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl FourRow {
     pub fn new() -> Self {
+        Self::with_config(BoardConfig::CLASSIC)
+    }
+
+    pub fn with_config(config: BoardConfig) -> Self {
         FourRow {
-            board: [[Tile::Empty; BOARD_WIDTH]; BOARD_HEIGHT],
+            board: [[Tile::Empty; MAX_WIDTH]; MAX_HEIGHT],
+            config,
             current_player: Player::Player1,
+            key: 0,
         }
     }
 
-    pub fn heuristic(&self) -> f32 {
-        let mut score = 0.0;
-
-        // Column position scores
-        let column_scores = [0.0005, 0.002, 0.004, 0.005, 0.004, 0.002, 0.0005];
+    /// Plays a move string in the common column-sequence notation, e.g.
+    /// `"4453"`, where each digit is a 1-based column.
+    pub fn from_moves(moves: &str) -> Result<FourRow, ParseError> {
+        FourRow::new().play_moves(moves)
+    }
 
-        // Score for tile positions
-        for row in 0..BOARD_HEIGHT {
-            for col in 0..BOARD_WIDTH {
-                match self.board[row][col] {
-                    Tile::Player1 => score += column_scores[col],
-                    Tile::Player2 => score -= column_scores[col],
-                    Tile::Empty => {}
-                }
+    /// Like `from_moves`, but continues from this position.
+    pub fn play_moves(&self, moves: &str) -> Result<FourRow, ParseError> {
+        let mut state = self.clone();
+        for (position, character) in moves.trim().chars().enumerate() {
+            let column = character
+                .to_digit(10)
+                .filter(|&digit| (1..=state.config.width as u32).contains(&digit))
+                .ok_or(ParseError::InvalidColumn {
+                    position,
+                    character,
+                })? as u8;
+            if state.is_terminal() {
+                return Err(ParseError::GameOver { position });
             }
+            let action = GameAction { column: column - 1 };
+            if !state.get_possible_actions().contains(&action) {
+                return Err(ParseError::ColumnFull { position, column });
+            }
+            state = state.apply_action(&action);
         }
+        Ok(state)
+    }
 
-        // Score for rows of 3
-        score += self.count_rows_of_three();
-
-        score
+    /// Writes the position as a move string, the inverse of `from_moves`.
+    ///
+    /// The board does not remember the order the discs were played in, so
+    /// this returns one order that reaches it. Returns `None` if the position
+    /// cannot come up in a real game.
+    pub fn to_moves(&self) -> Option<String> {
+        let mut moves = Vec::new();
+        if !self.find_moves(&mut moves, &mut HashSet::new()) {
+            return None;
+        }
+        Some(moves.iter().map(|column| (b'1' + column) as char).collect())
     }
 
-    fn count_rows_of_three(&self) -> f32 {
-        let mut score = 0.0;
+    /// Takes back discs of alternating players until the board is empty,
+    /// never passing through a position where the game was already over.
+    fn find_moves(&self, moves: &mut Vec<u8>, dead_ends: &mut HashSet<FourRow>) -> bool {
+        if self.board[0].iter().all(|&tile| tile == Tile::Empty) {
+            return self.current_player == Player::Player1;
+        }
 
-        // Check horizontal rows of 3
-        for row in 0..BOARD_HEIGHT {
-            for col in 0..BOARD_WIDTH - 2 {
-                if let Some(player_score) =
-                    self.check_three_in_line([(row, col), (row, col + 1), (row, col + 2)])
-                {
-                    score += player_score;
-                }
+        let last_player = self.current_player.switch();
+        for col in 0..self.config.width {
+            let Some(row) = (0..self.config.height)
+                .rev()
+                .find(|&row| self.board[row][col] != Tile::Empty)
+            else {
+                continue;
+            };
+            if self.board[row][col] != last_player.tile() {
+                continue;
             }
-        }
 
-        // Check vertical rows of 3
-        for row in 0..BOARD_HEIGHT - 2 {
-            for col in 0..BOARD_WIDTH {
-                if let Some(player_score) =
-                    self.check_three_in_line([(row, col), (row + 1, col), (row + 2, col)])
-                {
-                    score += player_score;
-                }
+            let mut previous = self.clone();
+            previous.board[row][col] = Tile::Empty;
+            previous.current_player = last_player;
+            previous.key ^= disc_key(row, col, last_player) ^ PLAYER2_KEY;
+            if previous.is_terminal() || dead_ends.contains(&previous) {
+                continue;
             }
+            if previous.find_moves(moves, dead_ends) {
+                moves.push(col as u8);
+                return true;
+            }
+            dead_ends.insert(previous);
         }
+        false
+    }
+
+    pub fn heuristic(&self) -> f32 {
+        self.centre_control() + self.count_open_rows()
+    }
 
-        // Check diagonal rows of 3 (bottom-left to top-right)
-        for row in 0..BOARD_HEIGHT - 2 {
-            for col in 0..BOARD_WIDTH - 2 {
-                if let Some(player_score) =
-                    self.check_three_in_line([(row, col), (row + 1, col + 1), (row + 2, col + 2)])
-                {
-                    score += player_score;
+    /// Score for tile positions, central columns are worth more.
+    pub fn centre_control(&self) -> f32 {
+        let mut score = 0.0;
+        for row in &self.board[..self.config.height] {
+            for (col, tile) in row[..self.config.width].iter().enumerate() {
+                match tile {
+                    Tile::Player1 => score += self.column_score(col),
+                    Tile::Player2 => score -= self.column_score(col),
+                    Tile::Empty => {}
                 }
             }
         }
+        score
+    }
+
+    fn column_score(&self, col: usize) -> f32 {
+        // Both middle columns of an even width count as the centre
+        let distance = (2 * col).abs_diff(self.config.width - 1) / 2;
+        CENTRE_SCORES.get(distance).copied().unwrap_or(0.0)
+    }
+
+    /// Scores every line of `win_length - 1` equal discs that can still be
+    /// extended on at least one side. On the classic board these are the
+    /// rows of three.
+    pub fn count_open_rows(&self) -> f32 {
+        let length = self.config.win_length - 1;
+        let mut score = 0.0;
 
-        // Check diagonal rows of 3 (top-left to bottom-right)
-        for row in 2..BOARD_HEIGHT {
-            for col in 0..BOARD_WIDTH - 2 {
-                if let Some(player_score) =
-                    self.check_three_in_line([(row, col), (row - 1, col + 1), (row - 2, col + 2)])
-                {
-                    score += player_score;
+        for row in 0..self.config.height {
+            for col in 0..self.config.width {
+                for direction in DIRECTIONS {
+                    if let Some(player_score) = self.check_open_row((row, col), direction, length) {
+                        score += player_score;
+                    }
                 }
             }
         }
@@ -117,49 +288,71 @@ impl FourRow {
         score
     }
 
-    fn check_three_in_line(&self, positions: [(usize, usize); 3]) -> Option<f32> {
-        let tiles: Vec<Tile> = positions
-            .iter()
-            .map(|&(row, col)| self.board[row][col])
-            .collect();
+    /// Whether all `length` tiles from `start` in `direction` are on the
+    /// board and hold `tile`.
+    fn is_line_of(
+        &self,
+        tile: Tile,
+        (row, col): (usize, usize),
+        (row_step, col_step): (i32, i32),
+        length: usize,
+    ) -> bool {
+        let last = length as i32 - 1;
+        let end = (row as i32 + row_step * last, col as i32 + col_step * last);
+        self.on_board(end).is_some()
+            && (0..=last).all(|i| {
+                let row = (row as i32 + row_step * i) as usize;
+                let col = (col as i32 + col_step * i) as usize;
+                self.board[row][col] == tile
+            })
+    }
 
-        // Check if all three tiles are the same and not empty
-        if tiles[0] != Tile::Empty && tiles[0] == tiles[1] && tiles[1] == tiles[2] {
-            // Check how many sides this line of 3 can be extended on
-            let extension_sides = self.count_extension_sides(&positions);
+    fn on_board(&self, (row, col): (i32, i32)) -> Option<(usize, usize)> {
+        let on_board = (0..self.config.height as i32).contains(&row)
+            && (0..self.config.width as i32).contains(&col);
+        on_board.then_some((row as usize, col as usize))
+    }
 
-            if extension_sides > 0 {
-                let score = if extension_sides == 2 { 0.08 } else { 0.05 };
-                match tiles[0] {
-                    Tile::Player1 => Some(score),
-                    Tile::Player2 => Some(-score),
-                    Tile::Empty => None,
-                }
-            } else {
-                None
-            }
-        } else {
-            None
+    fn check_open_row(
+        &self,
+        start: (usize, usize),
+        direction: (i32, i32),
+        length: usize,
+    ) -> Option<f32> {
+        let tile = self.board[start.0][start.1];
+
+        // Check if all tiles are the same and not empty
+        if tile == Tile::Empty || !self.is_line_of(tile, start, direction, length) {
+            return None;
         }
-    }
 
-    fn count_extension_sides(&self, positions: &[(usize, usize); 3]) -> usize {
-        // Calculate the direction vector
-        let (row_diff, col_diff) = (
-            positions[1].0 as i32 - positions[0].0 as i32,
-            positions[1].1 as i32 - positions[0].1 as i32,
-        );
+        // Check how many sides this line can be extended on
+        let extension_sides = self.count_extension_sides(start, direction, length);
+        if extension_sides == 0 {
+            return None;
+        }
+
+        let score = if extension_sides == 2 { 0.08 } else { 0.05 };
+        match tile {
+            Tile::Player1 => Some(score),
+            Tile::Player2 => Some(-score),
+            Tile::Empty => None,
+        }
+    }
 
+    fn count_extension_sides(
+        &self,
+        (row, col): (usize, usize),
+        (row_step, col_step): (i32, i32),
+        length: usize,
+    ) -> usize {
         // Check position before the first tile
-        let before_pos = (
-            positions[0].0 as i32 - row_diff,
-            positions[0].1 as i32 - col_diff,
-        );
+        let before_pos = (row as i32 - row_step, col as i32 - col_step);
 
         // Check position after the last tile
         let after_pos = (
-            positions[2].0 as i32 + row_diff,
-            positions[2].1 as i32 + col_diff,
+            row as i32 + row_step * length as i32,
+            col as i32 + col_step * length as i32,
         );
 
         let can_extend_before = self.is_valid_extension_position(before_pos);
@@ -169,99 +362,42 @@ impl FourRow {
     }
 
     fn is_valid_extension_position(&self, pos: (i32, i32)) -> bool {
-        let (row, col) = pos;
-
-        // Check if position is within bounds
-        if row < 0 || row >= BOARD_HEIGHT as i32 || col < 0 || col >= BOARD_WIDTH as i32 {
-            return false;
-        }
-
-        let row = row as usize;
-        let col = col as usize;
-
-        // Position must be empty
-        if self.board[row][col] != Tile::Empty {
-            return false;
-        }
-
-        true
+        // Position must be on the board and empty
+        self.on_board(pos)
+            .is_some_and(|(row, col)| self.board[row][col] == Tile::Empty)
     }
 
     pub fn get_terminal(&self) -> GameResult {
         // Check for a win condition
-        for row in 0..BOARD_HEIGHT {
-            for col in 0..BOARD_WIDTH {
-                if self.board[row][col] == Tile::Empty {
-                    continue;
-                }
+        for row in 0..self.config.height {
+            for col in 0..self.config.width {
                 let tile = self.board[row][col];
-
-                // Check horizontal
-                if col + 3 < BOARD_WIDTH
-                    && tile == self.board[row][col + 1]
-                    && tile == self.board[row][col + 2]
-                    && tile == self.board[row][col + 3]
-                {
-                    return if tile == Tile::Player1 {
-                        GameResult::Win(Player::Player1)
-                    } else {
-                        GameResult::Win(Player::Player2)
-                    };
-                }
-
-                // Check vertical
-                if row + 3 < BOARD_HEIGHT
-                    && tile == self.board[row + 1][col]
-                    && tile == self.board[row + 2][col]
-                    && tile == self.board[row + 3][col]
-                {
-                    return if tile == Tile::Player1 {
-                        GameResult::Win(Player::Player1)
-                    } else {
-                        GameResult::Win(Player::Player2)
-                    };
-                }
-
-                // Check diagonal (bottom-left to top-right)
-                if row + 3 < BOARD_HEIGHT
-                    && col + 3 < BOARD_WIDTH
-                    && tile == self.board[row + 1][col + 1]
-                    && tile == self.board[row + 2][col + 2]
-                    && tile == self.board[row + 3][col + 3]
-                {
-                    return if tile == Tile::Player1 {
-                        GameResult::Win(Player::Player1)
-                    } else {
-                        GameResult::Win(Player::Player2)
-                    };
+                if tile == Tile::Empty {
+                    continue;
                 }
 
-                // Check diagonal (top-left to bottom-right)
-                if row >= 3
-                    && col + 3 < BOARD_WIDTH
-                    && tile == self.board[row - 1][col + 1]
-                    && tile == self.board[row - 2][col + 2]
-                    && tile == self.board[row - 3][col + 3]
-                {
-                    return if tile == Tile::Player1 {
-                        GameResult::Win(Player::Player1)
-                    } else {
-                        GameResult::Win(Player::Player2)
-                    };
+                for direction in DIRECTIONS {
+                    if self.is_line_of(tile, (row, col), direction, self.config.win_length) {
+                        return if tile == Tile::Player1 {
+                            GameResult::Win(Player::Player1)
+                        } else {
+                            GameResult::Win(Player::Player2)
+                        };
+                    }
                 }
             }
         }
 
         // Check for a draw (no empty tiles left)
-        if self
-            .board
-            .iter()
-            .all(|row| row.iter().all(|&tile| tile != Tile::Empty))
-        {
+        if self.board[..self.config.height].iter().all(|row| {
+            row[..self.config.width]
+                .iter()
+                .all(|&tile| tile != Tile::Empty)
+        }) {
             return GameResult::Draw;
         }
 
-        return GameResult::Ongoing;
+        GameResult::Ongoing
     }
 }
 
@@ -269,28 +405,24 @@ impl GameState for FourRow {
     type Action = GameAction;
 
     fn get_possible_actions(&self) -> Vec<Self::Action> {
-        (0..BOARD_WIDTH)
-            .filter(|&col| self.board[BOARD_HEIGHT - 1][col] == Tile::Empty)
+        (0..self.config.width)
+            .filter(|&col| self.board[self.config.height - 1][col] == Tile::Empty)
             .map(|x| GameAction { column: x as u8 })
             .collect()
     }
 
     fn apply_action(&self, action: &Self::Action) -> Self {
-        let mut new_board = self.board;
-        for row in 0..BOARD_HEIGHT {
-            if new_board[row][action.column as usize] == Tile::Empty {
-                new_board[row][action.column as usize] = self.current_player.tile();
-                break;
-            }
-        }
-        FourRow {
-            board: new_board,
-            current_player: self.current_player.switch(),
-        }
+        let mut new_board = self.clone();
+        new_board.make_move(action);
+        new_board
     }
 
     fn is_terminal(&self) -> bool {
-        return self.get_terminal() != GameResult::Ongoing;
+        self.get_terminal() != GameResult::Ongoing
+    }
+
+    fn hash_key(&self) -> u64 {
+        self.key
     }
 
     fn evaluate(&self) -> Option<f32> {
@@ -301,6 +433,186 @@ impl GameState for FourRow {
             GameResult::Ongoing => {}
         }
         // Game is still ongoing
-        return Some(self.heuristic());
+        Some(self.heuristic())
+    }
+}
+
+/// The undo information is the column played, whose top disc is taken back.
+impl MakeMove for FourRow {
+    type Undo = GameAction;
+
+    fn make_move(&mut self, action: &GameAction) -> GameAction {
+        let col = action.column as usize;
+        if let Some(row) = (0..self.config.height).find(|&row| self.board[row][col] == Tile::Empty)
+        {
+            self.board[row][col] = self.current_player.tile();
+            self.key ^= disc_key(row, col, self.current_player);
+        }
+        self.current_player = self.current_player.switch();
+        self.key ^= PLAYER2_KEY;
+        *action
+    }
+
+    fn unmake_move(&mut self, undo: GameAction) {
+        let col = undo.column as usize;
+        self.current_player = self.current_player.switch();
+        self.key ^= PLAYER2_KEY;
+        if let Some(row) = (0..self.config.height)
+            .rev()
+            .find(|&row| self.board[row][col] != Tile::Empty)
+        {
+            self.board[row][col] = Tile::Empty;
+            self.key ^= disc_key(row, col, self.current_player);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(moves: &str) -> FourRow {
+        FourRow::from_moves(moves).unwrap()
+    }
+
+    #[test]
+    fn test_from_moves() {
+        let state = play("4453");
+        assert_eq!(state.board[0][3], Tile::Player1);
+        assert_eq!(state.board[1][3], Tile::Player2);
+        assert_eq!(state.board[0][4], Tile::Player1);
+        assert_eq!(state.board[0][2], Tile::Player2);
+        assert_eq!(state.current_player, Player::Player1);
+        assert_eq!(FourRow::from_moves(""), Ok(FourRow::new()));
+    }
+
+    #[test]
+    fn test_from_moves_errors() {
+        assert_eq!(
+            FourRow::from_moves("448"),
+            Err(ParseError::InvalidColumn {
+                position: 2,
+                character: '8'
+            })
+        );
+        assert_eq!(
+            FourRow::from_moves("40"),
+            Err(ParseError::InvalidColumn {
+                position: 1,
+                character: '0'
+            })
+        );
+        assert_eq!(
+            FourRow::from_moves("1111111"),
+            Err(ParseError::ColumnFull {
+                position: 6,
+                column: 1
+            })
+        );
+        // Player 1 wins vertically with the seventh move
+        assert_eq!(
+            FourRow::from_moves("12121213"),
+            Err(ParseError::GameOver { position: 7 })
+        );
+    }
+
+    #[test]
+    fn test_to_moves_round_trip() {
+        for moves in ["", "4453", "444444333333", "1212121", "3344556", "4455667"] {
+            let state = play(moves);
+            let exported = state.to_moves().unwrap();
+            assert_eq!(exported.len(), moves.len());
+            assert_eq!(FourRow::from_moves(&exported), Ok(state));
+        }
+    }
+
+    #[test]
+    fn test_to_moves_unreachable_position() {
+        let mut state = FourRow::new();
+        state.board[0][0] = Tile::Player2;
+        assert_eq!(state.to_moves(), None);
+    }
+
+    #[test]
+    fn test_hash_key_transpositions() {
+        assert_eq!(play("4453").hash_key(), play("5344").hash_key());
+        assert_eq!(play("").hash_key(), FourRow::new().hash_key());
+        assert_ne!(play("45").hash_key(), play("54").hash_key());
+        assert_ne!(play("4").hash_key(), play("44").hash_key());
+    }
+
+    #[test]
+    fn test_make_and_unmake_move() {
+        let mut state = play("4453");
+        let before = state.clone();
+        let mut made = Vec::new();
+        for column in [3, 3, 0, 6, 3] {
+            let action = GameAction { column };
+            let expected = state.apply_action(&action);
+            made.push(state.make_move(&action));
+            assert_eq!(state, expected);
+            assert_eq!(state.hash_key(), expected.hash_key());
+        }
+        while let Some(undo) = made.pop() {
+            state.unmake_move(undo);
+        }
+        assert_eq!(state, before);
+        assert_eq!(state.hash_key(), before.hash_key());
+    }
+
+    #[test]
+    fn test_board_config() {
+        assert_eq!(BoardConfig::new(7, 6, 4), Some(BoardConfig::CLASSIC));
+        assert!(BoardConfig::new(9, 7, 5).is_some());
+        assert_eq!(BoardConfig::new(10, 7, 4), None);
+        assert_eq!(BoardConfig::new(7, 6, 2), None);
+        assert_eq!(BoardConfig::new(4, 4, 5), None);
+    }
+
+    #[test]
+    fn test_larger_board() {
+        let config = BoardConfig::new(8, 7, 4).unwrap();
+        let state = FourRow::with_config(config);
+        assert_eq!(state.get_possible_actions().len(), 8);
+        assert_eq!(
+            state.play_moves("8888888").map(|state| state.to_moves()),
+            Ok(Some("8888888".to_string()))
+        );
+        assert_eq!(
+            state.play_moves("88888888"),
+            Err(ParseError::ColumnFull {
+                position: 7,
+                column: 8
+            })
+        );
+        assert_eq!(
+            state.play_moves("9"),
+            Err(ParseError::InvalidColumn {
+                position: 0,
+                character: '9'
+            })
+        );
+
+        // The two middle columns are worth the same
+        let left = state.play_moves("4").unwrap();
+        let right = state.play_moves("5").unwrap();
+        assert!(left.heuristic() > state.play_moves("3").unwrap().heuristic());
+        assert_eq!(left.heuristic(), right.heuristic());
+    }
+
+    #[test]
+    fn test_connect_five() {
+        let state = FourRow::with_config(BoardConfig::new(9, 7, 5).unwrap());
+        let four_in_a_row = state.play_moves("1122334").unwrap();
+        assert_eq!(four_in_a_row.get_terminal(), GameResult::Ongoing);
+        let five_in_a_row = four_in_a_row.play_moves("45").unwrap();
+        assert_eq!(
+            five_in_a_row.get_terminal(),
+            GameResult::Win(Player::Player1)
+        );
+        assert_eq!(five_in_a_row.to_moves().unwrap().len(), 9);
+
+        // Four in a row already counts as a threat to extend
+        assert!(four_in_a_row.heuristic() > state.play_moves("1122333").unwrap().heuristic());
     }
 }
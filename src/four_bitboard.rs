@@ -0,0 +1,328 @@
+//! Bitboard implementation of Connect Four
+//!
+//! Each player's discs are stored in a `u64`. Column `c` occupies bits
+//! `c * 7 .. c * 7 + 6`; the seventh bit of every column is a sentinel that
+//! is never set, so shifted masks cannot wrap from one column into the next.
+//! This makes move application O(1) and four-in-a-row detection a handful
+//! of shifts, while behaving exactly like [`FourRow`] on the classic 7x6
+//! board.
+
+use std::fmt::Debug;
+
+use crate::four::{BOARD_HEIGHT, BOARD_WIDTH, BoardConfig, FourRow, GameAction};
+use crate::minmax::{GameResult, GameState, MakeMove, Player, Tile};
+
+/// Bits per column including the sentinel.
+const COLUMN_BITS: usize = BOARD_HEIGHT + 1;
+
+/// Shifts between neighbouring cells: vertical, horizontal and both diagonals.
+const DIRECTIONS: [usize; 4] = [1, COLUMN_BITS, COLUMN_BITS - 1, COLUMN_BITS + 1];
+
+const fn column_mask(col: usize) -> u64 {
+    ((1 << BOARD_HEIGHT) - 1) << (col * COLUMN_BITS)
+}
+
+const fn board_mask() -> u64 {
+    let mut mask = 0;
+    let mut col = 0;
+    while col < BOARD_WIDTH {
+        mask |= column_mask(col);
+        col += 1;
+    }
+    mask
+}
+
+/// All playable cells, without the sentinel bits.
+const BOARD_MASK: u64 = board_mask();
+
+const COLUMN_SCORES: [f32; BOARD_WIDTH] = [0.0005, 0.002, 0.004, 0.005, 0.004, 0.002, 0.0005];
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FourRowBitboard {
+    pub player1: u64,
+    pub player2: u64,
+    pub heights: [u8; BOARD_WIDTH],
+    pub current_player: Player,
+}
+
+impl Debug for FourRowBitboard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in (0..BOARD_HEIGHT).rev() {
+            for col in 0..BOARD_WIDTH {
+                let symbol = match self.tile(row, col) {
+                    Tile::Empty => ".",
+                    Tile::Player1 => "X",
+                    Tile::Player2 => "O",
+                };
+                write!(f, "{} ", symbol)?;
+            }
+            writeln!(f)?;
+        }
+        for col in 0..BOARD_WIDTH {
+            write!(f, "{} ", col)?;
+        }
+        Ok(())
+    }
+}
+
+/// Error converting a `FourRow` that is not played on the classic board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnsupportedBoard(pub BoardConfig);
+
+impl TryFrom<&FourRow> for FourRowBitboard {
+    type Error = UnsupportedBoard;
+
+    fn try_from(state: &FourRow) -> Result<Self, Self::Error> {
+        if state.config != BoardConfig::CLASSIC {
+            return Err(UnsupportedBoard(state.config));
+        }
+
+        let mut bitboard = FourRowBitboard::new();
+        for (row, tiles) in state.board[..BOARD_HEIGHT].iter().enumerate() {
+            for (col, tile) in tiles[..BOARD_WIDTH].iter().enumerate() {
+                let bit = 1 << (col * COLUMN_BITS + row);
+                match tile {
+                    Tile::Player1 => bitboard.player1 |= bit,
+                    Tile::Player2 => bitboard.player2 |= bit,
+                    Tile::Empty => continue,
+                }
+                bitboard.heights[col] = bitboard.heights[col].max(row as u8 + 1);
+            }
+        }
+        bitboard.current_player = state.current_player;
+        Ok(bitboard)
+    }
+}
+
+impl Default for FourRowBitboard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FourRowBitboard {
+    pub fn new() -> Self {
+        FourRowBitboard {
+            player1: 0,
+            player2: 0,
+            heights: [0; BOARD_WIDTH],
+            current_player: Player::Player1,
+        }
+    }
+
+    pub fn tile(&self, row: usize, col: usize) -> Tile {
+        let bit = 1 << (col * COLUMN_BITS + row);
+        if self.player1 & bit != 0 {
+            Tile::Player1
+        } else if self.player2 & bit != 0 {
+            Tile::Player2
+        } else {
+            Tile::Empty
+        }
+    }
+
+    fn has_four(discs: u64) -> bool {
+        DIRECTIONS.iter().any(|&shift| {
+            let pairs = discs & (discs >> shift);
+            pairs & (pairs >> (2 * shift)) != 0
+        })
+    }
+
+    pub fn heuristic(&self) -> f32 {
+        let mut score = 0.0;
+
+        // Column position scores
+        for (col, column_score) in COLUMN_SCORES.iter().enumerate() {
+            let mask = column_mask(col);
+            let balance = (self.player1 & mask).count_ones() as f32
+                - (self.player2 & mask).count_ones() as f32;
+            score += balance * column_score;
+        }
+
+        // Score for rows of 3
+        score += Self::rows_of_three(self.player1, self.empty());
+        score -= Self::rows_of_three(self.player2, self.empty());
+
+        score
+    }
+
+    fn empty(&self) -> u64 {
+        BOARD_MASK & !(self.player1 | self.player2)
+    }
+
+    /// Scores every line of three `discs` that can still be extended on at
+    /// least one side, like `FourRow::count_open_rows`.
+    fn rows_of_three(discs: u64, empty: u64) -> f32 {
+        let mut score = 0.0;
+        for shift in DIRECTIONS {
+            // Bit `p` is set if `p`, `p + shift` and `p + 2 * shift` are all taken.
+            let threes = discs & (discs >> shift) & (discs >> (2 * shift));
+            let open_before = threes & (empty << shift);
+            let open_after = threes & (empty >> (3 * shift));
+            let open_both = (open_before & open_after).count_ones();
+            let open_one = (open_before ^ open_after).count_ones();
+            score += open_both as f32 * 0.08 + open_one as f32 * 0.05;
+        }
+        score
+    }
+
+    pub fn get_terminal(&self) -> GameResult {
+        if Self::has_four(self.player1) {
+            return GameResult::Win(Player::Player1);
+        }
+        if Self::has_four(self.player2) {
+            return GameResult::Win(Player::Player2);
+        }
+        if self
+            .heights
+            .iter()
+            .all(|&height| height as usize == BOARD_HEIGHT)
+        {
+            return GameResult::Draw;
+        }
+        GameResult::Ongoing
+    }
+}
+
+impl GameState for FourRowBitboard {
+    type Action = GameAction;
+
+    fn get_possible_actions(&self) -> Vec<Self::Action> {
+        (0..BOARD_WIDTH)
+            .filter(|&col| (self.heights[col] as usize) < BOARD_HEIGHT)
+            .map(|col| GameAction { column: col as u8 })
+            .collect()
+    }
+
+    fn apply_action(&self, action: &Self::Action) -> Self {
+        let mut next = *self;
+        next.make_move(action);
+        next
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.get_terminal() != GameResult::Ongoing
+    }
+
+    fn hash_key(&self) -> u64 {
+        // Adding the occupied cells to Player1's discs gives every column a
+        // distinct pattern that fits in its 7 bits, so this is unique.
+        self.player1 + (self.player1 | self.player2)
+    }
+
+    fn evaluate(&self) -> Option<f32> {
+        match self.get_terminal() {
+            GameResult::Win(Player::Player1) => return Some(100.0),
+            GameResult::Win(Player::Player2) => return Some(-100.0),
+            GameResult::Draw => return Some(0.0),
+            GameResult::Ongoing => {}
+        }
+        // Game is still ongoing
+        Some(self.heuristic())
+    }
+}
+
+impl MakeMove for FourRowBitboard {
+    type Undo = GameAction;
+
+    fn make_move(&mut self, action: &GameAction) -> GameAction {
+        let col = action.column as usize;
+        let bit = 1 << (col * COLUMN_BITS + self.heights[col] as usize);
+        match self.current_player {
+            Player::Player1 => self.player1 |= bit,
+            Player::Player2 => self.player2 |= bit,
+        }
+        self.heights[col] += 1;
+        self.current_player = self.current_player.switch();
+        *action
+    }
+
+    fn unmake_move(&mut self, undo: GameAction) {
+        let col = undo.column as usize;
+        self.heights[col] -= 1;
+        let bit = 1 << (col * COLUMN_BITS + self.heights[col] as usize);
+        self.current_player = self.current_player.switch();
+        match self.current_player {
+            Player::Player1 => self.player1 &= !bit,
+            Player::Player2 => self.player2 &= !bit,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play<T: GameState<Action = GameAction>>(start: T, columns: &[u8]) -> T {
+        columns.iter().fold(start, |state, &column| {
+            state.apply_action(&GameAction { column })
+        })
+    }
+
+    /// Plays pseudo-random games on both boards and checks that they agree
+    /// after every move.
+    #[test]
+    fn test_matches_array_board() {
+        let mut rng: u64 = 0x2545_f491_4f6c_dd1d;
+        for _ in 0..200 {
+            let mut array = FourRow::new();
+            let mut bitboard = FourRowBitboard::new();
+            loop {
+                assert_eq!(bitboard, FourRowBitboard::try_from(&array).unwrap());
+                assert_eq!(bitboard.get_terminal(), array.get_terminal(), "{:?}", array);
+                assert_eq!(
+                    bitboard.get_possible_actions(),
+                    array.get_possible_actions()
+                );
+                let (expected, actual) = (array.evaluate().unwrap(), bitboard.evaluate().unwrap());
+                assert!(
+                    (expected - actual).abs() < 1e-5,
+                    "{} != {}\n{:?}",
+                    expected,
+                    actual,
+                    array
+                );
+                if array.is_terminal() {
+                    break;
+                }
+
+                let actions = array.get_possible_actions();
+                rng ^= rng << 13;
+                rng ^= rng >> 7;
+                rng ^= rng << 17;
+                let action = actions[rng as usize % actions.len()];
+                array = array.apply_action(&action);
+                bitboard = bitboard.apply_action(&action);
+            }
+        }
+    }
+
+    #[test]
+    fn test_detects_all_directions() {
+        let wins: [&[u8]; 4] = [
+            &[0, 1, 0, 1, 0, 1, 0],             // vertical
+            &[0, 0, 1, 1, 2, 2, 3],             // horizontal
+            &[0, 1, 1, 2, 2, 3, 2, 3, 3, 6, 3], // diagonal up
+            &[3, 2, 2, 1, 1, 0, 1, 0, 0, 6, 0], // diagonal down
+        ];
+        for moves in wins {
+            let state = play(FourRowBitboard::new(), moves);
+            assert_eq!(
+                state.get_terminal(),
+                GameResult::Win(Player::Player1),
+                "{:?}",
+                state
+            );
+        }
+    }
+
+    #[test]
+    fn test_no_wrap_between_columns() {
+        // Player1 fills the top of column 0 and the bottom of column 1, which
+        // are adjacent bits apart from the sentinel in between.
+        let moves = [0, 0, 0, 1, 0, 1, 1, 2, 1, 0, 2, 0, 1];
+        let state = play(FourRowBitboard::new(), &moves);
+        let array = play(FourRow::new(), &moves);
+        assert_eq!(state.get_terminal(), array.get_terminal());
+    }
+}
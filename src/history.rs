@@ -0,0 +1,113 @@
+//! Undo/redo history for the game bindings
+//!
+//! Stores a snapshot after every ply together with a cursor to the current
+//! one. Undoing moves the cursor back, redoing moves it forward again, and
+//! recording a new move drops every snapshot after the cursor.
+
+#[derive(Debug, Clone)]
+pub struct History<T> {
+    snapshots: Vec<T>,
+    cursor: usize,
+}
+
+impl<T: Clone> History<T> {
+    pub fn new(initial: T) -> Self {
+        History {
+            snapshots: vec![initial],
+            cursor: 0,
+        }
+    }
+
+    pub fn current(&self) -> &T {
+        &self.snapshots[self.cursor]
+    }
+
+    /// Records the snapshot after a new ply, discarding the redo entries.
+    pub fn push(&mut self, snapshot: T) {
+        self.snapshots.truncate(self.cursor + 1);
+        self.snapshots.push(snapshot);
+        self.cursor += 1;
+    }
+
+    /// Steps back up to `plies` snapshots and returns how many were undone.
+    pub fn undo(&mut self, plies: usize) -> usize {
+        let steps = plies.min(self.cursor);
+        self.cursor -= steps;
+        steps
+    }
+
+    /// Steps forward up to `plies` snapshots and returns how many were redone.
+    pub fn redo(&mut self, plies: usize) -> usize {
+        let steps = plies.min(self.snapshots.len() - 1 - self.cursor);
+        self.cursor += steps;
+        steps
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.cursor > 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.cursor + 1 < self.snapshots.len()
+    }
+
+    /// Snapshots from the start up to and including the current one.
+    pub fn played(&self) -> &[T] {
+        &self.snapshots[..=self.cursor]
+    }
+
+    /// Number of plies played to reach the current snapshot.
+    pub fn moves_played(&self) -> usize {
+        self.cursor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undo_redo() {
+        let mut history = History::new(0);
+        history.push(1);
+        history.push(2);
+        history.push(3);
+        assert_eq!(history.moves_played(), 3);
+
+        assert_eq!(history.undo(2), 2);
+        assert_eq!(*history.current(), 1);
+        assert!(history.can_redo());
+
+        assert_eq!(history.redo(1), 1);
+        assert_eq!(*history.current(), 2);
+        assert_eq!(history.moves_played(), 2);
+        assert_eq!(history.played(), &[0, 1, 2]);
+    }
+
+    #[test]
+    fn test_undo_and_redo_stop_at_the_ends() {
+        let mut history = History::new("start");
+        assert!(!history.can_undo());
+        assert_eq!(history.undo(1), 0);
+
+        history.push("first");
+        assert_eq!(history.undo(2), 1);
+        assert_eq!(*history.current(), "start");
+        assert_eq!(history.redo(5), 1);
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn test_push_discards_redo_entries() {
+        let mut history = History::new(0);
+        history.push(1);
+        history.push(2);
+        history.undo(2);
+        history.push(10);
+        assert!(!history.can_redo());
+        assert_eq!(history.moves_played(), 1);
+        assert_eq!(*history.current(), 10);
+        history.undo(1);
+        assert_eq!(*history.current(), 0);
+    }
+}
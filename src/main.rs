@@ -1,50 +1,652 @@
-mod four;
-mod minmax;
-mod checkers;
-
-use crate::minmax::{GameResult, GameState, Player};
-
-fn main() {
-    let mut state = checkers::CheckersBoard::new();
-
-    while !state.is_terminal() {
-        println!("{:?}", state);
-        println!("Current Player: {:?}", state.current_player);
-
-        if state.current_player == Player::Player1 {
-            println!("AI 1 is thinking...");
-            let mut explored_states = std::collections::HashMap::new();
-            let game_tree = minmax::minmax(
-                state,
-                15,
-                true,
-                &mut explored_states,
-                f32::NEG_INFINITY,
-                f32::INFINITY,
-            );
-            state = game_tree
-                .best_child(true, &explored_states)
-                .expect("No valid moves found")
-                .clone();
-            println!("Evaluation: {}", game_tree.evaluation);
+mod play;
+mod tournament;
+
+use std::{
+    io::IsTerminal,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use fourrow::book::{self, BookConfig};
+use fourrow::checkers::CheckersBoard;
+use fourrow::four::FourRow;
+use fourrow::four_bitboard::FourRowBitboard;
+use fourrow::tablebase::{MAX_PIECES, Tablebase, TablebaseBoard};
+use fourrow::{pdn, perft};
+
+use crate::play::{Controller, Playable, SearchLimit};
+use crate::tournament::{Sprt, TournamentConfig};
+
+const USAGE: &str = "\
+Usage: fourrow <GAME> [OPTIONS]
+       fourrow tournament <GAME> [TOURNAMENT OPTIONS]
+       fourrow perft <GAME> <DEPTH> [PERFT OPTIONS]
+       fourrow book <GAME> <FILE> [BOOK OPTIONS]
+       fourrow tablebase <PIECES> <FILE>
+
+Games:
+  connect-four   Play Connect Four, moves are column numbers like 4
+  checkers       Play checkers, moves are PDN squares like 11-15 or 22x15
+
+Options:
+  --player1 <human|ai>   Who plays first (default: human)
+  --player2 <human|ai>   Who plays second (default: ai)
+  --depth <N>            Search depth of both AI players (default: 8)
+  --depth1, --depth2 <N> Search depth of one AI player
+  --time <SECONDS>       Thinking time per move of both AI players
+  --time1, --time2 <SECONDS>
+                         Thinking time per move of one AI player
+  --tablebase <FILE>     Checkers endgame tablebase for the AI players
+  --no-color             Print the board without colours
+  -h, --help             Print this help
+
+Type 'quit' at the move prompt to stop.
+
+Tournament options:
+  --engine1, --engine2 <SPEC>
+                         Engine settings as comma separated key=value pairs:
+                         depth=N, time=SECONDS, mcts, iterations=N, eval=NAME,
+                         weights=W1:W2:... (default: depth=6)
+  --games <N>            Number of games, rounded up to an even number
+                         (default: 100)
+  --opening-plies <N>    Start from every position this many plies in
+                         (default: 2)
+  --sprt <ELO0,ELO1>     Stop early once H0: elo0 or H1: elo1 is accepted
+  --alpha, --beta <P>    Error probabilities of the SPRT (default: 0.05)
+
+Perft options:
+  --divide               Print the count below every first move
+  --fen <FEN>            Checkers position to start from
+  --moves <MOVES>        Connect Four moves to start from, like 4453
+
+Book options:
+  --plies <N>            Give book moves for the first N plies (default: 4)
+  --depth <N>            Search depth used to value the moves (default: 10)
+  --margin <SCORE>       Also keep moves this much worse than the best
+                         (default: 0.5)
+
+The tablebase command solves every checkers position with up to PIECES
+pieces and writes the result to FILE.";
+
+const DEFAULT_DEPTH: u8 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Game {
+    ConnectFour,
+    Checkers,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Options {
+    game: Game,
+    players: [Controller; 2],
+    colour: bool,
+    tablebase: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Command {
+    Play(Options),
+    Tournament(Game, TournamentConfig),
+    Perft(PerftOptions),
+    Book(Game, PathBuf, BookConfig),
+    Tablebase(u8, PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct PerftOptions {
+    game: Game,
+    depth: u8,
+    divide: bool,
+    /// FEN or move string of the start position, depending on the game.
+    position: Option<String>,
+}
+
+fn parse_game(arg: Option<&String>) -> Result<Game, String> {
+    match arg.map(String::as_str) {
+        Some("connect-four") => Ok(Game::ConnectFour),
+        Some("checkers") => Ok(Game::Checkers),
+        Some(other) => Err(format!("unknown game '{}'", other)),
+        None => Err("no game given".to_string()),
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Command, String> {
+    match args.first().map(String::as_str) {
+        Some("tournament") => parse_tournament_args(&args[1..]),
+        Some("perft") => parse_perft_args(&args[1..]),
+        Some("book") => parse_book_args(&args[1..]),
+        Some("tablebase") => parse_tablebase_args(&args[1..]),
+        _ => parse_play_args(args).map(Command::Play),
+    }
+}
+
+fn parse_play_args(args: &[String]) -> Result<Options, String> {
+    let mut args = args.iter();
+    let game = parse_game(args.next())?;
+
+    let mut humans = [true, false];
+    let mut limits = [SearchLimit::Depth(DEFAULT_DEPTH); 2];
+    let mut colour = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    let mut tablebase = None;
+
+    while let Some(flag) = args.next() {
+        if flag == "--no-color" {
+            colour = false;
+            continue;
+        }
+        if flag == "--tablebase" && game == Game::Checkers {
+            let path = args
+                .next()
+                .ok_or_else(|| format!("{} needs a value", flag))?;
+            tablebase = Some(PathBuf::from(path));
+            continue;
+        }
+
+        let sides: &[usize] = match flag.as_str() {
+            "--player1" | "--depth1" | "--time1" => &[0],
+            "--player2" | "--depth2" | "--time2" => &[1],
+            "--depth" | "--time" => &[0, 1],
+            _ => return Err(format!("unknown option '{}'", flag)),
+        };
+        let value = args
+            .next()
+            .ok_or_else(|| format!("{} needs a value", flag))?;
+
+        for &side in sides {
+            if flag.starts_with("--player") {
+                humans[side] = match value.as_str() {
+                    "human" => true,
+                    "ai" => false,
+                    _ => return Err(format!("{} must be 'human' or 'ai', not '{}'", flag, value)),
+                };
+            } else if flag.starts_with("--depth") {
+                let depth = value
+                    .parse::<u8>()
+                    .ok()
+                    .filter(|&depth| depth > 0)
+                    .ok_or_else(|| format!("invalid depth '{}'", value))?;
+                limits[side] = SearchLimit::Depth(depth);
+            } else {
+                let seconds = value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|&seconds| seconds > 0.0 && seconds.is_finite())
+                    .ok_or_else(|| format!("invalid time '{}'", value))?;
+                limits[side] = SearchLimit::Time(Duration::from_secs_f64(seconds));
+            }
+        }
+    }
+
+    let controller = |side: usize| {
+        if humans[side] {
+            Controller::Human
         } else {
-            println!("AI 2 is thinking...");
-            let mut explored_states = std::collections::HashMap::new();
-            let game_tree = minmax::minmax(
-                state,
-                15,
-                false,
-                &mut explored_states,
-                f32::NEG_INFINITY,
-                f32::INFINITY,
-            );
-            state = game_tree
-                .best_child(false, &explored_states)
-                .expect("No valid moves found")
-                .clone();
-            println!("Evaluation: {}", game_tree.evaluation);
-        }
-    }
-
-    println!("{:?}", state);
+            Controller::Ai(limits[side])
+        }
+    };
+    Ok(Options {
+        game,
+        players: [controller(0), controller(1)],
+        colour,
+        tablebase,
+    })
+}
+
+fn parse_tournament_args(args: &[String]) -> Result<Command, String> {
+    let mut args = args.iter();
+    let game = parse_game(args.next())?;
+    let mut config = TournamentConfig {
+        engines: ["".parse()?, "".parse()?],
+        games: 100,
+        opening_plies: 2,
+        sprt: None,
+    };
+    let (mut alpha, mut beta) = (None, None);
+
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("{} needs a value", flag))?;
+        let invalid = || format!("invalid value '{}' for {}", value, flag);
+        match flag.as_str() {
+            "--engine1" => config.engines[0] = value.parse()?,
+            "--engine2" => config.engines[1] = value.parse()?,
+            "--games" => {
+                config.games = value
+                    .parse()
+                    .ok()
+                    .filter(|&games| games > 0)
+                    .ok_or_else(invalid)?
+            }
+            "--opening-plies" => config.opening_plies = value.parse().map_err(|_| invalid())?,
+            "--sprt" => {
+                let (elo0, elo1) = value
+                    .split_once(',')
+                    .and_then(|(elo0, elo1)| Some((elo0.parse().ok()?, elo1.parse().ok()?)))
+                    .filter(|(elo0, elo1): &(f64, f64)| elo0 < elo1)
+                    .ok_or_else(invalid)?;
+                config.sprt = Some(Sprt::new(elo0, elo1));
+            }
+            "--alpha" | "--beta" => {
+                let probability = value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|&p| p > 0.0 && p < 0.5)
+                    .ok_or_else(invalid)?;
+                if flag == "--alpha" {
+                    alpha = Some(probability);
+                } else {
+                    beta = Some(probability);
+                }
+            }
+            _ => return Err(format!("unknown option '{}'", flag)),
+        }
+    }
+
+    match &mut config.sprt {
+        Some(sprt) => {
+            sprt.alpha = alpha.unwrap_or(sprt.alpha);
+            sprt.beta = beta.unwrap_or(sprt.beta);
+        }
+        None if alpha.is_some() || beta.is_some() => {
+            return Err("--alpha and --beta need --sprt".to_string());
+        }
+        None => {}
+    }
+    Ok(Command::Tournament(game, config))
+}
+
+fn parse_perft_args(args: &[String]) -> Result<Command, String> {
+    let mut args = args.iter();
+    let game = parse_game(args.next())?;
+    let depth = args.next().ok_or("no depth given")?;
+    let depth = depth
+        .parse()
+        .map_err(|_| format!("invalid depth '{}'", depth))?;
+    let mut options = PerftOptions {
+        game,
+        depth,
+        divide: false,
+        position: None,
+    };
+
+    while let Some(flag) = args.next() {
+        match (flag.as_str(), game) {
+            ("--divide", _) => options.divide = true,
+            ("--fen", Game::Checkers) | ("--moves", Game::ConnectFour) => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("{} needs a value", flag))?;
+                options.position = Some(value.clone());
+            }
+            _ => return Err(format!("unknown option '{}'", flag)),
+        }
+    }
+    Ok(Command::Perft(options))
+}
+
+fn parse_book_args(args: &[String]) -> Result<Command, String> {
+    let mut args = args.iter();
+    let game = parse_game(args.next())?;
+    let path = args.next().ok_or("no book file given")?;
+    let mut config = BookConfig {
+        plies: 4,
+        depth: 10,
+        margin: 0.5,
+    };
+
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("{} needs a value", flag))?;
+        let invalid = || format!("invalid value '{}' for {}", value, flag);
+        match flag.as_str() {
+            "--plies" => config.plies = value.parse().map_err(|_| invalid())?,
+            "--depth" => {
+                config.depth = value
+                    .parse()
+                    .ok()
+                    .filter(|&depth| depth > 0)
+                    .ok_or_else(invalid)?
+            }
+            "--margin" => {
+                config.margin = value
+                    .parse()
+                    .ok()
+                    .filter(|&margin: &f32| margin >= 0.0 && margin.is_finite())
+                    .ok_or_else(invalid)?
+            }
+            _ => return Err(format!("unknown option '{}'", flag)),
+        }
+    }
+    Ok(Command::Book(game, PathBuf::from(path), config))
+}
+
+fn parse_tablebase_args(args: &[String]) -> Result<Command, String> {
+    let pieces = args.first().ok_or("no number of pieces given")?;
+    let pieces = pieces
+        .parse()
+        .ok()
+        .filter(|pieces| (2..=MAX_PIECES).contains(pieces))
+        .ok_or_else(|| {
+            format!(
+                "number of pieces must be from 2 to {}, not '{}'",
+                MAX_PIECES, pieces
+            )
+        })?;
+    let path = args.get(1).ok_or("no tablebase file given")?;
+    if let Some(arg) = args.get(2) {
+        return Err(format!("unknown option '{}'", arg));
+    }
+    Ok(Command::Tablebase(pieces, PathBuf::from(path)))
+}
+
+fn run_tablebase(pieces: u8, path: &Path) -> Result<(), String> {
+    let start = Instant::now();
+    let bytes = Tablebase::generate(pieces)
+        .map_err(|err| err.to_string())?
+        .to_bytes();
+    std::fs::write(path, &bytes)
+        .map_err(|err| format!("cannot write '{}': {}", path.display(), err))?;
+    println!(
+        "Wrote {} bytes in {:.1}s",
+        bytes.len(),
+        start.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
+
+fn read_tablebase(path: &Path) -> Result<Arc<Tablebase>, String> {
+    let bytes =
+        std::fs::read(path).map_err(|err| format!("cannot read '{}': {}", path.display(), err))?;
+    Tablebase::from_bytes(&bytes)
+        .map(Arc::new)
+        .map_err(|err| format!("'{}': {}", path.display(), err))
+}
+
+/// Builds a book from the start position and writes it to `path`. Connect
+/// Four books are keyed by the bitboard, which the engine searches on.
+fn run_book(game: Game, path: &Path, config: &BookConfig) -> Result<(), String> {
+    let start = Instant::now();
+    let opening_book = match game {
+        Game::ConnectFour => {
+            let state = FourRowBitboard::try_from(&FourRow::new())
+                .expect("the classic board fits in a bitboard");
+            book::build(&state, true, config)
+        }
+        Game::Checkers => book::build(&CheckersBoard::new(), true, config),
+    };
+    let bytes = opening_book.to_bytes();
+    std::fs::write(path, &bytes)
+        .map_err(|err| format!("cannot write '{}': {}", path.display(), err))?;
+    println!(
+        "Wrote {} positions ({} bytes) in {:.1}s",
+        opening_book.len(),
+        bytes.len(),
+        start.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
+
+fn run_perft(options: &PerftOptions) -> Result<(), String> {
+    match options.game {
+        Game::ConnectFour => {
+            let moves = options.position.as_deref().unwrap_or("");
+            let state = FourRow::from_moves(moves).map_err(|err| err.to_string())?;
+            print_perft(&state, options.depth, options.divide);
+        }
+        Game::Checkers => {
+            let state = match &options.position {
+                Some(fen) => pdn::parse_fen(fen).map_err(|err| err.to_string())?,
+                None => CheckersBoard::new(),
+            };
+            print_perft(&state, options.depth, options.divide);
+        }
+    }
+    Ok(())
+}
+
+fn print_perft<T: Playable>(state: &T, depth: u8, divide: bool)
+where
+    T::Action: Send + Sync,
+{
+    let start = Instant::now();
+    let nodes = if divide {
+        let split = perft::divide(state, depth);
+        for (action, count) in &split {
+            println!("{}: {}", state.format_move(action), count);
+        }
+        split.iter().map(|(_, count)| count).sum()
+    } else {
+        perft::perft(state, depth)
+    };
+    let elapsed = start.elapsed();
+    println!("Nodes: {}", nodes);
+    println!(
+        "Time: {:.3}s ({:.0} nodes/s)",
+        elapsed.as_secs_f64(),
+        nodes as f64 / elapsed.as_secs_f64().max(1e-9)
+    );
+}
+
+fn play_game(options: &Options) -> Result<(), String> {
+    let (players, colour) = (options.players, options.colour);
+    match (options.game, &options.tablebase) {
+        (Game::ConnectFour, _) => play::play(FourRow::new(), players, colour),
+        (Game::Checkers, None) => play::play(CheckersBoard::new(), players, colour),
+        (Game::Checkers, Some(path)) => {
+            let state = TablebaseBoard {
+                board: CheckersBoard::new(),
+                tablebase: read_tablebase(path)?,
+            };
+            play::play(state, players, colour)
+        }
+    }
+    .map_err(|err| err.to_string())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    let command = match parse_args(&args) {
+        Ok(command) => command,
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let result = match command {
+        Command::Play(options) => play_game(&options),
+        Command::Tournament(game, config) => match game {
+            Game::ConnectFour => tournament::run(&FourRow::new(), &config),
+            Game::Checkers => tournament::run(&CheckersBoard::new(), &config),
+        }
+        .map(|_| ()),
+        Command::Perft(options) => run_perft(&options),
+        Command::Book(game, path, config) => run_book(game, &path, &config),
+        Command::Tablebase(pieces, path) => run_tablebase(pieces, &path),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &str) -> Vec<String> {
+        args.split_whitespace().map(String::from).collect()
+    }
+
+    fn parse(args_text: &str) -> Result<Options, String> {
+        match parse_args(&args(args_text))? {
+            Command::Play(options) => Ok(options),
+            command => panic!("expected to play, got {:?}", command),
+        }
+    }
+
+    #[test]
+    fn test_parse_defaults() {
+        let options = parse("checkers --no-color").unwrap();
+        assert_eq!(options.game, Game::Checkers);
+        assert_eq!(
+            options.players,
+            [
+                Controller::Human,
+                Controller::Ai(SearchLimit::Depth(DEFAULT_DEPTH))
+            ]
+        );
+        assert!(!options.colour);
+    }
+
+    #[test]
+    fn test_parse_limits_per_side() {
+        let options = parse("connect-four --player1 ai --depth 5 --time2 1.5").unwrap();
+        assert_eq!(options.game, Game::ConnectFour);
+        assert_eq!(
+            options.players,
+            [
+                Controller::Ai(SearchLimit::Depth(5)),
+                Controller::Ai(SearchLimit::Time(Duration::from_millis(1500)))
+            ]
+        );
+
+        let options = parse("checkers --player2 human --depth1 3").unwrap();
+        assert_eq!(options.players, [Controller::Human, Controller::Human]);
+
+        let options = parse("checkers --tablebase endgames.tb").unwrap();
+        assert_eq!(options.tablebase, Some(PathBuf::from("endgames.tb")));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse("").unwrap_err(), "no game given");
+        assert_eq!(parse("chess").unwrap_err(), "unknown game 'chess'");
+        assert_eq!(
+            parse("checkers --depth").unwrap_err(),
+            "--depth needs a value"
+        );
+        assert_eq!(
+            parse("checkers --depth 0").unwrap_err(),
+            "invalid depth '0'"
+        );
+        assert_eq!(
+            parse("checkers --time -1").unwrap_err(),
+            "invalid time '-1'"
+        );
+        assert_eq!(
+            parse("checkers --player1 robot").unwrap_err(),
+            "--player1 must be 'human' or 'ai', not 'robot'"
+        );
+        assert_eq!(
+            parse("checkers --fast").unwrap_err(),
+            "unknown option '--fast'"
+        );
+        assert_eq!(
+            parse("connect-four --tablebase endgames.tb").unwrap_err(),
+            "unknown option '--tablebase'"
+        );
+    }
+
+    #[test]
+    fn test_parse_tournament() {
+        let Ok(Command::Tournament(game, config)) = parse_args(&args(
+            "tournament checkers --engine1 depth=4,eval=material --games 20 --sprt 0,50 --alpha 0.1",
+        )) else {
+            panic!("expected a tournament");
+        };
+        assert_eq!(game, Game::Checkers);
+        assert_eq!(config.engines[0].heuristic, "material");
+        assert_eq!(config.engines[1], "depth=6".parse().unwrap());
+        assert_eq!(config.games, 20);
+        assert_eq!(config.opening_plies, 2);
+        let sprt = config.sprt.unwrap();
+        assert_eq!(
+            (sprt.elo0, sprt.elo1, sprt.alpha, sprt.beta),
+            (0.0, 50.0, 0.1, 0.05)
+        );
+
+        assert_eq!(
+            parse_args(&args("tournament connect-four --sprt 10,0")),
+            Err("invalid value '10,0' for --sprt".to_string())
+        );
+        assert_eq!(
+            parse_args(&args("tournament connect-four --beta 0.1")),
+            Err("--alpha and --beta need --sprt".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_perft() {
+        assert_eq!(
+            parse_args(&args("perft checkers 6 --divide --fen B:W21-32:B1-12")),
+            Ok(Command::Perft(PerftOptions {
+                game: Game::Checkers,
+                depth: 6,
+                divide: true,
+                position: Some("B:W21-32:B1-12".to_string()),
+            }))
+        );
+        assert_eq!(
+            parse_args(&args("perft connect-four")),
+            Err("no depth given".to_string())
+        );
+        assert_eq!(
+            parse_args(&args("perft connect-four 5 --fen B:W21:B1")),
+            Err("unknown option '--fen'".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_book() {
+        assert_eq!(
+            parse_args(&args("book checkers out.book --plies 6 --margin 0")),
+            Ok(Command::Book(
+                Game::Checkers,
+                PathBuf::from("out.book"),
+                BookConfig {
+                    plies: 6,
+                    depth: 10,
+                    margin: 0.0,
+                }
+            ))
+        );
+        assert_eq!(
+            parse_args(&args("book connect-four")),
+            Err("no book file given".to_string())
+        );
+        assert_eq!(
+            parse_args(&args("book connect-four out.book --margin -1")),
+            Err("invalid value '-1' for --margin".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_tablebase() {
+        assert_eq!(
+            parse_args(&args("tablebase 4 endgames.tb")),
+            Ok(Command::Tablebase(4, PathBuf::from("endgames.tb")))
+        );
+        assert_eq!(
+            parse_args(&args("tablebase 9 endgames.tb")),
+            Err("number of pieces must be from 2 to 5, not '9'".to_string())
+        );
+        assert_eq!(
+            parse_args(&args("tablebase 3")),
+            Err("no tablebase file given".to_string())
+        );
+    }
 }
@@ -0,0 +1,377 @@
+//! Monte Carlo tree search with the UCT selection rule
+//!
+//! Unlike `minmax` this needs no heuristic: positions are valued by playing
+//! random moves until the game ends. Only the sign of `GameState::evaluate`
+//! on terminal positions is used, positive meaning a win for Player1.
+//!
+//! The tree is kept in a `Vec` of nodes that refer to each other by index.
+//! Every node stores the results of the playouts through it from the point
+//! of view of the player who made the move leading to it, so selection can
+//! always pick the child with the highest UCT score.
+
+use std::time::Duration;
+
+use crate::minmax::{Deadline, GameState, SearchResult};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MctsConfig {
+    /// Weight of the exploration term in UCT, `sqrt(2)` in the textbook
+    /// version. Larger values try rarely visited moves more often.
+    pub exploration: f32,
+    /// Stop after this many playouts.
+    pub max_iterations: u32,
+    /// Stop once this much time has passed, if set.
+    pub time_budget: Option<Duration>,
+    /// Seed of the random playouts, so searches can be repeated.
+    pub seed: u64,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        MctsConfig {
+            exploration: std::f32::consts::SQRT_2,
+            max_iterations: 10_000,
+            time_budget: None,
+            seed: 0x2545_f491_4f6c_dd1d,
+        }
+    }
+}
+
+/// xorshift64*, good enough to pick random moves.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // xorshift gets stuck on zero
+        Rng(seed.max(1))
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 32) as usize % bound
+    }
+}
+
+struct Node<T: GameState> {
+    state: T,
+    /// Move from the parent to this node, `None` for the root.
+    action: Option<T::Action>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried_actions: Vec<T::Action>,
+    /// Whether Player1 is to move in `state`.
+    maximizing_player: bool,
+    visits: u32,
+    /// Sum of the playout rewards for the player who moved into this node,
+    /// 1 for a win, 0.5 for a draw and 0 for a loss.
+    reward: f32,
+}
+
+impl<T: GameState> Node<T> {
+    fn new(
+        state: T,
+        action: Option<T::Action>,
+        parent: Option<usize>,
+        maximizing_player: bool,
+    ) -> Self {
+        let untried_actions = if state.is_terminal() {
+            Vec::new()
+        } else {
+            state.get_possible_actions()
+        };
+        Node {
+            state,
+            action,
+            parent,
+            children: Vec::new(),
+            untried_actions,
+            maximizing_player,
+            visits: 0,
+            reward: 0.0,
+        }
+    }
+
+    fn mean_reward(&self) -> f32 {
+        self.reward / self.visits.max(1) as f32
+    }
+}
+
+/// Runs playouts from `state` until `config.max_iterations` or
+/// `config.time_budget` is used up and returns the most visited move.
+///
+/// `maximizing_player` is true if Player1 is to move. The evaluation is the
+/// expected result for Player1, from -1 for a certain loss to 1 for a
+/// certain win. The principal variation follows the most visited moves and
+/// `depth` is its length.
+pub fn mcts<T: GameState + Clone>(
+    state: &T,
+    maximizing_player: bool,
+    config: &MctsConfig,
+) -> SearchResult<T::Action> {
+    let deadline = config.time_budget.map(Deadline::after);
+    let mut search = SlicedSearch::new(state, maximizing_player, config);
+    while !search.is_finished() && !deadline.as_ref().is_some_and(Deadline::expired) {
+        search.step(1);
+    }
+    search.result()
+}
+
+/// An MCTS search that runs its playouts a few at a time, so the caller can
+/// do other work in between. `config.time_budget` is ignored, the caller
+/// decides when to stop.
+pub struct SlicedSearch<T: GameState> {
+    nodes: Vec<Node<T>>,
+    rng: Rng,
+    exploration: f32,
+    maximizing_player: bool,
+    iterations: u32,
+    max_iterations: u32,
+}
+
+impl<T: GameState + Clone> SlicedSearch<T> {
+    pub fn new(state: &T, maximizing_player: bool, config: &MctsConfig) -> Self {
+        SlicedSearch {
+            nodes: vec![Node::new(state.clone(), None, None, maximizing_player)],
+            rng: Rng::new(config.seed),
+            exploration: config.exploration,
+            maximizing_player,
+            iterations: 0,
+            max_iterations: config.max_iterations,
+        }
+    }
+
+    /// Runs up to `playouts` more playouts. Returns true once
+    /// `config.max_iterations` have been run.
+    pub fn step(&mut self, playouts: u32) -> bool {
+        for _ in 0..playouts {
+            if self.is_finished() {
+                break;
+            }
+            let leaf = select(&self.nodes, 0, self.exploration);
+            let leaf = expand(&mut self.nodes, leaf, &mut self.rng);
+            let reward = playout(&self.nodes[leaf].state, &mut self.rng);
+            backpropagate(&mut self.nodes, leaf, reward);
+            self.iterations += 1;
+        }
+        self.is_finished()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.iterations >= self.max_iterations
+    }
+
+    /// Number of playouts run so far.
+    pub fn iterations(&self) -> u32 {
+        self.iterations
+    }
+
+    /// The most visited move after the playouts run so far.
+    pub fn result(&self) -> SearchResult<T::Action> {
+        let nodes = &self.nodes;
+        let mut principal_variation = Vec::new();
+        let mut node = 0;
+        while let Some(child) = most_visited_child(nodes, node) {
+            principal_variation.extend(nodes[child].action.clone());
+            node = child;
+        }
+
+        let evaluation = match most_visited_child(nodes, 0) {
+            Some(child) => {
+                let reward = nodes[child].mean_reward();
+                let player1_reward = if self.maximizing_player {
+                    reward
+                } else {
+                    1.0 - reward
+                };
+                2.0 * player1_reward - 1.0
+            }
+            None => terminal_reward(&nodes[0].state) * 2.0 - 1.0,
+        };
+
+        SearchResult {
+            best_action: principal_variation.first().cloned(),
+            evaluation,
+            depth: principal_variation.len().min(u8::MAX as usize) as u8,
+            principal_variation,
+        }
+    }
+}
+
+/// Descends through fully expanded nodes by UCT score until reaching a node
+/// with untried moves or a terminal one.
+fn select<T: GameState>(nodes: &[Node<T>], mut node: usize, exploration: f32) -> usize {
+    while nodes[node].untried_actions.is_empty() && !nodes[node].children.is_empty() {
+        let log_visits = (nodes[node].visits as f32).ln();
+        let uct = |child: &usize| {
+            let child = &nodes[*child];
+            child.mean_reward() + exploration * (log_visits / child.visits as f32).sqrt()
+        };
+        node = *nodes[node]
+            .children
+            .iter()
+            .max_by(|a, b| uct(a).total_cmp(&uct(b)))
+            .expect("node has children");
+    }
+    node
+}
+
+/// Adds a child for one random untried move of `node` and returns it, or
+/// `node` itself if it is terminal.
+fn expand<T: GameState + Clone>(nodes: &mut Vec<Node<T>>, node: usize, rng: &mut Rng) -> usize {
+    let untried = &mut nodes[node].untried_actions;
+    if untried.is_empty() {
+        return node;
+    }
+    let action = untried.swap_remove(rng.below(untried.len()));
+
+    let state = nodes[node].state.apply_action(&action);
+    let maximizing_player = !nodes[node].maximizing_player;
+    nodes.push(Node::new(
+        state,
+        Some(action),
+        Some(node),
+        maximizing_player,
+    ));
+    let child = nodes.len() - 1;
+    nodes[node].children.push(child);
+    child
+}
+
+/// Plays random moves until the game ends and returns the reward for
+/// Player1.
+fn playout<T: GameState + Clone>(state: &T, rng: &mut Rng) -> f32 {
+    let mut state = state.clone();
+    while !state.is_terminal() {
+        let actions = state.get_possible_actions();
+        if actions.is_empty() {
+            break;
+        }
+        state = state.apply_action(&actions[rng.below(actions.len())]);
+    }
+    terminal_reward(&state)
+}
+
+/// 1 if Player1 won, 0 if Player2 won and 0.5 for a draw.
+fn terminal_reward<T: GameState>(state: &T) -> f32 {
+    match state.evaluate() {
+        Some(evaluation) if evaluation > 0.0 => 1.0,
+        Some(evaluation) if evaluation < 0.0 => 0.0,
+        _ => 0.5,
+    }
+}
+
+fn backpropagate<T: GameState>(nodes: &mut [Node<T>], leaf: usize, player1_reward: f32) {
+    let mut current = Some(leaf);
+    while let Some(node) = current {
+        let parent = nodes[node].parent;
+        nodes[node].visits += 1;
+        // The player who moved into this node is the one to move in the parent
+        if let Some(parent) = parent {
+            nodes[node].reward += if nodes[parent].maximizing_player {
+                player1_reward
+            } else {
+                1.0 - player1_reward
+            };
+        }
+        current = parent;
+    }
+}
+
+fn most_visited_child<T: GameState>(nodes: &[Node<T>], node: usize) -> Option<usize> {
+    nodes[node]
+        .children
+        .iter()
+        .copied()
+        .max_by_key(|&child| nodes[child].visits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::four::{FourRow, GameAction};
+
+    fn play(moves: &str) -> FourRow {
+        FourRow::from_moves(moves).unwrap()
+    }
+
+    fn config(max_iterations: u32) -> MctsConfig {
+        MctsConfig {
+            max_iterations,
+            ..MctsConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_takes_immediate_win() {
+        // Player1 has three in column 4 and wins by playing it again
+        let state = play("434343");
+        let result = mcts(&state, true, &config(2_000));
+        assert_eq!(result.best_action, Some(GameAction { column: 3 }));
+        assert!(result.evaluation > 0.5);
+    }
+
+    #[test]
+    fn test_blocks_opponent_win() {
+        // Player2 must stop three in a row on the bottom row
+        let state = play("17273");
+        let result = mcts(&state, false, &config(5_000));
+        assert_eq!(result.best_action, Some(GameAction { column: 3 }));
+    }
+
+    #[test]
+    fn test_same_seed_same_result() {
+        let state = play("4455");
+        let first = mcts(&state, true, &config(500));
+        let second = mcts(&state, true, &config(500));
+        assert_eq!(first, second);
+        assert!(!first.principal_variation.is_empty());
+        assert_eq!(first.depth as usize, first.principal_variation.len());
+    }
+
+    #[test]
+    fn test_sliced_search_matches_mcts() {
+        let state = play("4455");
+        let config = config(1_000);
+        let mut search = SlicedSearch::new(&state, true, &config);
+        let mut slices = 0;
+        while !search.step(64) {
+            slices += 1;
+        }
+        assert_eq!(slices, 15);
+        assert_eq!(search.iterations(), 1_000);
+        assert_eq!(search.result(), mcts(&state, true, &config));
+        assert!(search.step(64));
+        assert_eq!(search.iterations(), 1_000);
+    }
+
+    #[test]
+    fn test_terminal_position() {
+        let state = play("1212121");
+        let result = mcts(&state, false, &config(100));
+        assert_eq!(result.best_action, None);
+        assert_eq!(result.evaluation, 1.0);
+    }
+
+    #[test]
+    fn test_time_budget() {
+        let config = MctsConfig {
+            max_iterations: u32::MAX,
+            time_budget: Some(Duration::from_millis(50)),
+            ..MctsConfig::default()
+        };
+        let result = mcts(&FourRow::new(), true, &config);
+        assert!(result.best_action.is_some());
+    }
+
+    #[test]
+    fn test_checkers_move_is_legal() {
+        use crate::checkers::CheckersBoard;
+
+        let state = CheckersBoard::new();
+        let result = mcts(&state, true, &config(200));
+        let action = result.best_action.unwrap();
+        assert!(state.get_possible_actions().contains(&action));
+    }
+}
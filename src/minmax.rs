@@ -1,5 +1,10 @@
-use std::{collections::HashMap, fmt::Debug, hash::Hash};
 use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    hash::{BuildHasherDefault, Hash, Hasher},
+    time::Duration,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Tile {
@@ -38,185 +43,1218 @@ impl Player {
 }
 
 pub trait GameState {
-    type Action;
+    type Action: Clone + PartialEq;
 
     fn get_possible_actions(&self) -> Vec<Self::Action>;
     fn apply_action(&self, action: &Self::Action) -> Self;
     fn is_terminal(&self) -> bool;
     fn evaluate(&self) -> Option<f32>;
+
+    /// Key identifying the position in transposition tables.
+    ///
+    /// Equal positions must have equal keys, different positions should
+    /// only collide by chance. Implementations keep a Zobrist key up to date
+    /// in `apply_action` so this is free to call.
+    fn hash_key(&self) -> u64;
+
+    /// Whether `evaluate` can be trusted in this position.
+    ///
+    /// When the search runs out of depth in a position that is not quiet it
+    /// keeps following `get_possible_actions` until it reaches a quiet one.
+    /// This is meant for forced sequences such as mandatory captures, which
+    /// must end eventually. The default never extends the search.
+    fn is_quiet(&self) -> bool {
+        true
+    }
+
+    /// Exact value of the position if it is known without searching, e.g.
+    /// from an endgame tablebase, from Player1's point of view like
+    /// `evaluate`.
+    ///
+    /// The alpha-beta searches take it for the positions their moves lead
+    /// to instead of searching them, so it must only be `Some` where it is
+    /// the true value of the game. The default knows no positions.
+    fn probe(&self) -> Option<f32> {
+        None
+    }
+
+    /// Whether the position is terminal only because of how the game got
+    /// here, e.g. a draw by repetition, rather than because of what is on
+    /// the board. Searches do not reuse values that rely on such positions
+    /// for other paths to the same key. The default has none.
+    fn is_path_dependent(&self) -> bool {
+        false
+    }
 }
 
+/// In-place moves for games where copying the position in `apply_action`
+/// is a noticeable part of the search.
+///
+/// `make_move` must leave the position exactly as `apply_action` would,
+/// including the hash key, and `unmake_move` must restore it exactly.
+pub trait MakeMove: GameState {
+    /// What `unmake_move` needs to take a move back.
+    type Undo;
+
+    fn make_move(&mut self, action: &Self::Action) -> Self::Undo;
+
+    /// Takes back the last move made, whose `make_move` returned `undo`.
+    fn unmake_move(&mut self, undo: Self::Undo);
+}
+
+/// Outcome of a search from the point of view of the root position.
 #[derive(Debug, Clone, PartialEq)]
-pub struct GameTree<T: GameState> {
-    pub state: T,
-    pub children: Vec<T>,
+pub struct SearchResult<A> {
+    /// Move to play, `None` if the root position is terminal.
+    pub best_action: Option<A>,
     pub evaluation: f32,
+    /// Expected line of play starting with `best_action`.
+    pub principal_variation: Vec<A>,
+    /// Depth of the deepest completed search.
+    pub depth: u8,
 }
 
-pub struct MainLineItem<'a, T: GameState> {
-    pub state: &'a T,
+/// Exact value of one legal move, see `analyze`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoveAnalysis<A> {
+    pub action: A,
     pub evaluation: f32,
+    /// Expected line of play starting with `action`.
+    pub principal_variation: Vec<A>,
 }
 
-impl<T: GameState + Eq + Hash> GameTree<T> {
-    pub fn new(state: T) -> Self {
-        GameTree {
-            state,
-            children: Vec::new(),
-            evaluation: 0.0,
+/// How a stored evaluation relates to the true minimax value of a position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    /// The search finished inside its window, the value is exact.
+    Exact,
+    /// The search failed high, the true value is at least this large.
+    Lower,
+    /// The search failed low, the true value is at most this large.
+    Upper,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableEntry<A> {
+    pub depth: u8,
+    pub bound: Bound,
+    pub evaluation: f32,
+    pub best_action: Option<A>,
+}
+
+/// Hasher for maps keyed by `GameState::hash_key`. The keys are already
+/// random, so they are used as they are.
+#[derive(Default)]
+pub struct KeyHasher(u64);
+
+impl Hasher for KeyHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = self.0.rotate_left(8) ^ byte as u64;
         }
     }
 
-    pub fn get_children(&self) -> &Vec<T> {
-        &self.children
+    fn write_u64(&mut self, key: u64) {
+        self.0 = key;
     }
+}
 
-    pub fn get_evaluation(&self) -> f32 {
-        self.evaluation
+/// Map from `GameState::hash_key` to `V`.
+pub type KeyMap<V> = HashMap<u64, V, BuildHasherDefault<KeyHasher>>;
+
+/// `N` pseudo-random keys for Zobrist hashing, generated with splitmix64
+/// so that every build uses the same ones.
+pub const fn zobrist_keys<const N: usize>(seed: u64) -> [u64; N] {
+    let mut keys = [0; N];
+    let mut state = seed;
+    let mut i = 0;
+    while i < N {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        keys[i] = z ^ (z >> 31);
+        i += 1;
     }
+    keys
+}
 
-    pub fn get_main_line<'a>(
-        &'a self,
-        explored_states: &'a HashMap<T, GameTree<T>>,
-    ) -> Vec<MainLineItem<'a, T>> {
-        let mut main_line = Vec::new();
-        let current_node: &T = &self.state;
-        main_line.push(MainLineItem {
-            state: current_node,
-            evaluation: explored_states
-                .get(current_node)
-                .map_or(0.0, |tree| tree.evaluation),
-        });
+/// Slots in a `TranspositionTable::new` table.
+pub const DEFAULT_TABLE_ENTRIES: usize = 1 << 18;
 
-        if self.children.is_empty() {
-            return main_line;
-        }
+/// Cache of search results keyed by `GameState::hash_key`.
+///
+/// Entries remember the depth they were searched to and whether their value
+/// is exact or only a bound, so they are only reused where that is sound.
+/// A position is assumed to always be searched for the same side.
+///
+/// The table has a fixed number of slots, each holding one position. A new
+/// entry replaces the one in its slot if that is for the same position or
+/// was not searched deeper, so the most expensive results are kept.
+pub struct TranspositionTable<T: GameState> {
+    slots: Vec<Option<(u64, TableEntry<T::Action>)>>,
+}
 
-        for child in &self.children {
-            if let Some(child_tree) = explored_states.get(child) {
-                if child_tree.evaluation == self.evaluation {
-                    main_line.extend(child_tree.get_main_line(explored_states));
-                    break;
-                }
-            }
+impl<T: GameState> TranspositionTable<T> {
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_TABLE_ENTRIES)
+    }
+
+    /// A table with `entries` slots, at least one.
+    pub fn with_capacity(entries: usize) -> Self {
+        TranspositionTable {
+            slots: std::iter::repeat_with(|| None)
+                .take(entries.max(1))
+                .collect(),
         }
+    }
 
-        return main_line;
+    fn index(&self, key: u64) -> usize {
+        (key % self.slots.len() as u64) as usize
     }
 
-    pub fn best_child(
-        &self,
-        maximizing_player: bool,
-        explored_states: &HashMap<T, GameTree<T>>,
-    ) -> Option<&T> {
-        if self.children.is_empty() {
-            return None;
+    pub fn get(&self, state: &T) -> Option<&TableEntry<T::Action>> {
+        let key = state.hash_key();
+        match &self.slots[self.index(key)] {
+            Some((stored, entry)) if *stored == key => Some(entry),
+            _ => None,
         }
+    }
 
-        let mut best_child = None;
-        let mut best_evaluation = if maximizing_player {
-            f32::NEG_INFINITY
-        } else {
-            f32::INFINITY
+    pub fn insert(&mut self, state: &T, entry: TableEntry<T::Action>) {
+        let key = state.hash_key();
+        let index = self.index(key);
+        let slot = &mut self.slots[index];
+        let replace = match slot {
+            Some((stored, old)) => *stored == key || old.depth <= entry.depth,
+            None => true,
         };
+        if replace {
+            *slot = Some((key, entry));
+        }
+    }
+}
 
-        for child in &self.children {
-            if let Some(child_tree) = explored_states.get(child) {
-                if (maximizing_player && child_tree.evaluation > best_evaluation)
-                    || (!maximizing_player && child_tree.evaluation < best_evaluation)
-                {
-                    best_evaluation = child_tree.evaluation;
-                    best_child = Some(child);
-                }
-            }
+impl<T: GameState> Default for TranspositionTable<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Storage used by `alpha_beta` for its results.
+///
+/// `TranspositionTable` is the plain single-threaded implementation; other
+/// implementations can share one table between several searches.
+pub trait Table<T: GameState> {
+    fn probe(&self, state: &T) -> Option<TableEntry<T::Action>>;
+    fn store(&mut self, state: &T, entry: TableEntry<T::Action>);
+}
+
+impl<T: GameState> Table<T> for TranspositionTable<T> {
+    fn probe(&self, state: &T) -> Option<TableEntry<T::Action>> {
+        self.get(state).cloned()
+    }
+
+    fn store(&mut self, state: &T, entry: TableEntry<T::Action>) {
+        self.insert(state, entry);
+    }
+}
+
+/// Wall-clock deadline for time-limited searches.
+///
+/// `std::time::Instant` panics on `wasm32-unknown-unknown`, so the browser
+/// clock is used there instead.
+#[derive(Debug, Clone, Copy)]
+pub struct Deadline {
+    end_ms: f64,
+}
+
+impl Deadline {
+    pub fn after(budget: Duration) -> Self {
+        Deadline {
+            end_ms: now_ms() + budget.as_secs_f64() * 1000.0,
         }
+    }
 
-        best_child
+    pub fn expired(&self) -> bool {
+        now_ms() >= self.end_ms
     }
 }
 
-pub fn minmax<'a, T: GameState + Hash + Eq + Clone + Debug>(
+#[cfg(target_arch = "wasm32")]
+fn now_ms() -> f64 {
+    js_sys::Date::now()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now_ms() -> f64 {
+    use std::{sync::OnceLock, time::Instant};
+
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_secs_f64() * 1000.0
+}
+
+pub fn minmax<T: GameState + Clone>(
+    state: &T,
+    depth: u8,
+    maximizing_player: bool,
+    table: &mut TranspositionTable<T>,
+    alpha: f32,
+    beta: f32,
+) -> SearchResult<T::Action> {
+    let evaluation = alpha_beta(state, depth, maximizing_player, table, alpha, beta, None)
+        .expect("search without a deadline always completes");
+    search_result(state, depth, evaluation, table)
+}
+
+/// `minmax` for games with in-place moves, see `alpha_beta_in_place`.
+pub fn minmax_in_place<T: MakeMove + Clone>(
+    state: &T,
+    depth: u8,
+    maximizing_player: bool,
+    table: &mut TranspositionTable<T>,
+    alpha: f32,
+    beta: f32,
+) -> SearchResult<T::Action> {
+    minmax_until(state, depth, maximizing_player, table, alpha, beta, None)
+        .expect("search without a deadline always completes")
+}
+
+/// Searches depth 1, 2, 3, ... until `budget` runs out or `max_depth` is
+/// reached and returns the result of the deepest completed iteration.
+///
+/// Depth 1 is always searched to completion so there is a move to play
+/// even with a zero budget. The transposition table is shared between
+/// iterations, so each one starts with the best moves of the previous.
+pub fn iterative_deepening<T: MakeMove + Clone>(
+    state: &T,
+    maximizing_player: bool,
+    budget: Duration,
+    max_depth: u8,
+) -> SearchResult<T::Action> {
+    let deadline = Deadline::after(budget);
+    let mut table = TranspositionTable::new();
+    let mut result = minmax_in_place(
+        state,
+        1,
+        maximizing_player,
+        &mut table,
+        f32::NEG_INFINITY,
+        f32::INFINITY,
+    );
+
+    for depth in 2..=max_depth {
+        if deadline.expired() {
+            break;
+        }
+        match minmax_until(
+            state,
+            depth,
+            maximizing_player,
+            &mut table,
+            f32::NEG_INFINITY,
+            f32::INFINITY,
+            Some(&deadline),
+        ) {
+            Some(deeper) => result = deeper,
+            None => break,
+        }
+    }
+
+    result
+}
+
+/// Iterative deepening that runs a slice of time at a time, for callers that
+/// must regularly give control back, like the browser's main thread.
+///
+/// A slice that runs out mid-iteration throws away the unfinished search,
+/// but the subtrees it completed stay in the transposition table, so the
+/// next slice picks the iteration up close to where it stopped.
+pub struct SlicedSearch<T: MakeMove> {
     state: T,
+    maximizing_player: bool,
+    max_depth: u8,
+    table: TranspositionTable<T>,
+    result: Option<SearchResult<T::Action>>,
+}
+
+impl<T: MakeMove + Clone> SlicedSearch<T> {
+    pub fn new(state: &T, maximizing_player: bool, max_depth: u8) -> Self {
+        SlicedSearch {
+            state: state.clone(),
+            maximizing_player,
+            max_depth,
+            table: TranspositionTable::new(),
+            result: None,
+        }
+    }
+
+    /// Searches for at most `slice`, returning after the first iteration
+    /// that completes. Returns true once `max_depth` has been searched.
+    ///
+    /// Each slice first has to search back down to where the previous one
+    /// stopped, so slices much shorter than that never finish an iteration.
+    pub fn step(&mut self, slice: Duration) -> bool {
+        if self.is_finished() {
+            return true;
+        }
+        let depth = self.result.as_ref().map_or(1, |result| result.depth + 1);
+        if let Some(result) = minmax_until(
+            &self.state,
+            depth,
+            self.maximizing_player,
+            &mut self.table,
+            f32::NEG_INFINITY,
+            f32::INFINITY,
+            Some(&Deadline::after(slice)),
+        ) {
+            self.result = Some(result);
+        }
+        self.is_finished()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.result
+            .as_ref()
+            .is_some_and(|result| result.depth >= self.max_depth)
+    }
+
+    /// Result of the deepest completed iteration so far.
+    pub fn result(&self) -> Option<&SearchResult<T::Action>> {
+        self.result.as_ref()
+    }
+}
+
+/// In-place alpha-beta search that gives up and returns `None` once
+/// `deadline` expires.
+fn minmax_until<T: MakeMove + Clone>(
+    state: &T,
+    depth: u8,
+    maximizing_player: bool,
+    table: &mut TranspositionTable<T>,
+    alpha: f32,
+    beta: f32,
+    deadline: Option<&Deadline>,
+) -> Option<SearchResult<T::Action>> {
+    let evaluation = alpha_beta_in_place(
+        &mut state.clone(),
+        depth,
+        maximizing_player,
+        table,
+        alpha,
+        beta,
+        deadline,
+    )?;
+    Some(search_result(state, depth, evaluation, table))
+}
+
+fn search_result<T: GameState, S: Table<T>>(
+    state: &T,
+    depth: u8,
+    evaluation: f32,
+    table: &S,
+) -> SearchResult<T::Action> {
+    let principal_variation = principal_variation(state, depth, table);
+    SearchResult {
+        best_action: principal_variation.first().cloned(),
+        evaluation,
+        principal_variation,
+        depth,
+    }
+}
+
+/// Values every legal move in `state` by searching it to `depth` plies in
+/// total, best move for the side to move first. Unlike `minmax`, every move
+/// gets an exact value rather than just a bound. Equal moves keep the order
+/// of `get_possible_actions`.
+pub fn analyze<T: MakeMove + Clone>(
+    state: &T,
+    depth: u8,
+    maximizing_player: bool,
+) -> Vec<MoveAnalysis<T::Action>> {
+    if state.is_terminal() {
+        return Vec::new();
+    }
+
+    // Full windows keep every value exact, so one table serves all moves
+    let mut table = TranspositionTable::new();
+    let child_depth = depth.max(1) - 1;
+    let mut moves: Vec<_> = state
+        .get_possible_actions()
+        .into_iter()
+        .map(|action| {
+            let mut next_state = state.clone();
+            next_state.make_move(&action);
+            let evaluation = alpha_beta_in_place(
+                &mut next_state,
+                child_depth,
+                !maximizing_player,
+                &mut table,
+                f32::NEG_INFINITY,
+                f32::INFINITY,
+                None,
+            )
+            .expect("search without a deadline always completes");
+            let mut line = vec![action.clone()];
+            line.extend(principal_variation(&next_state, child_depth, &table));
+            MoveAnalysis {
+                action,
+                evaluation,
+                principal_variation: line,
+            }
+        })
+        .collect();
+
+    if maximizing_player {
+        moves.sort_by(|a, b| b.evaluation.total_cmp(&a.evaluation));
+    } else {
+        moves.sort_by(|a, b| a.evaluation.total_cmp(&b.evaluation));
+    }
+    moves
+}
+
+/// Follows the best moves stored in `table` from `state` for at most
+/// `depth` plies.
+pub fn principal_variation<T: GameState, S: Table<T>>(
+    state: &T,
+    depth: u8,
+    table: &S,
+) -> Vec<T::Action> {
+    let mut line = Vec::new();
+    let mut current = None;
+    while line.len() < depth as usize {
+        let position = current.as_ref().unwrap_or(state);
+        if position.is_terminal() {
+            break;
+        }
+        let Some(action) = table.probe(position).and_then(|entry| entry.best_action) else {
+            break;
+        };
+        current = Some(position.apply_action(&action));
+        line.push(action);
+    }
+    line
+}
+
+/// Value of a position found by the searches below.
+#[derive(Debug, Clone, Copy)]
+struct Searched {
+    evaluation: f32,
+    /// Whether a position that `GameState::is_path_dependent` was reached,
+    /// so the value only holds for the path searched.
+    path_dependent: bool,
+}
+
+impl From<f32> for Searched {
+    /// A value that holds however the position is reached, e.g. one from
+    /// the table or `GameState::probe`.
+    fn from(evaluation: f32) -> Self {
+        Searched {
+            evaluation,
+            path_dependent: false,
+        }
+    }
+}
+
+impl Searched {
+    /// Value of a position the search does not look past.
+    fn leaf<T: GameState>(state: &T) -> Self {
+        Searched {
+            evaluation: state.evaluate().unwrap_or(0.0),
+            path_dependent: state.is_path_dependent(),
+        }
+    }
+}
+
+/// Stores the result of searching `state`. A value relying on the path is
+/// stored at depth 0, so it is never reused and only orders the moves and
+/// gives the principal variation.
+fn store<T: GameState, S: Table<T>>(
+    table: &mut S,
+    state: &T,
+    depth: u8,
+    bound: Bound,
+    searched: Searched,
+    best_action: Option<T::Action>,
+) {
+    table.store(
+        state,
+        TableEntry {
+            depth: if searched.path_dependent { 0 } else { depth },
+            bound,
+            evaluation: searched.evaluation,
+            best_action,
+        },
+    );
+}
+
+/// Fail-soft alpha-beta search of `state` that returns its value, or `None`
+/// if `deadline` expired first. Results are cached in `table`.
+pub fn alpha_beta<T: GameState + Clone, S: Table<T>>(
+    state: &T,
+    depth: u8,
+    maximizing_player: bool,
+    table: &mut S,
+    alpha: f32,
+    beta: f32,
+    deadline: Option<&Deadline>,
+) -> Option<f32> {
+    search(
+        state,
+        depth,
+        maximizing_player,
+        table,
+        alpha,
+        beta,
+        deadline,
+    )
+    .map(|searched| searched.evaluation)
+}
+
+/// `alpha_beta`, also telling whether the value relies on the path.
+fn search<T: GameState + Clone, S: Table<T>>(
+    state: &T,
     depth: u8,
     maximizing_player: bool,
-    explored_states: &mut HashMap<T, GameTree<T>>,
+    table: &mut S,
     mut alpha: f32,
     mut beta: f32,
-) -> GameTree<T> {
-    // Min max algorithm with alpha-beta pruning
-    let mut tree = GameTree {
-        state: state,
-        children: Vec::new(),
-        evaluation: if maximizing_player {
-            f32::NEG_INFINITY
+    deadline: Option<&Deadline>,
+) -> Option<Searched> {
+    if deadline.is_some_and(Deadline::expired) {
+        return None;
+    }
+
+    if depth == 0 {
+        return quiescence(state, maximizing_player, alpha, beta, deadline);
+    }
+    if state.is_terminal() {
+        return Some(Searched::leaf(state));
+    }
+
+    let mut hash_action = None;
+    if let Some(entry) = table.probe(state) {
+        if entry.depth >= depth {
+            match entry.bound {
+                Bound::Exact => return Some(Searched::from(entry.evaluation)),
+                Bound::Lower => alpha = alpha.max(entry.evaluation),
+                Bound::Upper => beta = beta.min(entry.evaluation),
+            }
+            if alpha >= beta {
+                return Some(Searched::from(entry.evaluation));
+            }
+        }
+        hash_action = entry.best_action;
+    }
+
+    // Try the best move of an earlier search first, then the rest ordered by
+    // their static evaluation.
+    let mut next_states: Vec<_> = state
+        .get_possible_actions()
+        .into_iter()
+        .map(|action| {
+            let next_state = state.apply_action(&action);
+            let eval = next_state.evaluate().unwrap_or(0.0);
+            let score = if hash_action.as_ref() == Some(&action) {
+                f32::INFINITY
+            } else if maximizing_player {
+                eval
+            } else {
+                -eval
+            };
+            (action, next_state, score)
+        })
+        .collect();
+    next_states.sort_by(|a, b| {
+        a.2.partial_cmp(&b.2)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .reverse()
+    });
+
+    let mut evaluation = if maximizing_player {
+        f32::NEG_INFINITY
+    } else {
+        f32::INFINITY
+    };
+    let mut best_action = None;
+    let mut path_dependent = false;
+    let (window_alpha, window_beta) = (alpha, beta);
+
+    for (action, next_state, _) in next_states {
+        let child = match next_state.probe() {
+            Some(value) => Searched::from(value),
+            None => search(
+                &next_state,
+                depth - 1,
+                !maximizing_player,
+                table,
+                alpha,
+                beta,
+                deadline,
+            )?,
+        };
+        let child_evaluation = child.evaluation;
+        path_dependent |= child.path_dependent;
+
+        if maximizing_player {
+            if child_evaluation > evaluation || best_action.is_none() {
+                evaluation = child_evaluation;
+                best_action = Some(action);
+            }
+            alpha = alpha.max(evaluation);
         } else {
-            f32::INFINITY
-        },
+            if child_evaluation < evaluation || best_action.is_none() {
+                evaluation = child_evaluation;
+                best_action = Some(action);
+            }
+            beta = beta.min(evaluation);
+        }
+
+        if alpha >= beta {
+            break; // alpha-beta cutoff
+        }
+    }
+
+    let bound = if evaluation <= window_alpha {
+        Bound::Upper
+    } else if evaluation >= window_beta {
+        Bound::Lower
+    } else {
+        Bound::Exact
+    };
+    let searched = Searched {
+        evaluation,
+        path_dependent,
+    };
+    store(table, state, depth, bound, searched, best_action);
+
+    Some(searched)
+}
+
+/// Searches on from a leaf of `alpha_beta` until the position is quiet, so
+/// it is never valued in the middle of a forced sequence.
+///
+/// There is no transposition table here; the forced lines are short.
+fn quiescence<T: GameState>(
+    state: &T,
+    maximizing_player: bool,
+    mut alpha: f32,
+    mut beta: f32,
+    deadline: Option<&Deadline>,
+) -> Option<Searched> {
+    if deadline.is_some_and(Deadline::expired) {
+        return None;
+    }
+
+    if state.is_terminal() || state.is_quiet() {
+        return Some(Searched::leaf(state));
+    }
+
+    let mut evaluation = if maximizing_player {
+        f32::NEG_INFINITY
+    } else {
+        f32::INFINITY
     };
+    let mut path_dependent = false;
+    for action in state.get_possible_actions() {
+        let child = quiescence(
+            &state.apply_action(&action),
+            !maximizing_player,
+            alpha,
+            beta,
+            deadline,
+        )?;
+        let child_evaluation = child.evaluation;
+        path_dependent |= child.path_dependent;
+
+        if maximizing_player {
+            evaluation = evaluation.max(child_evaluation);
+            alpha = alpha.max(evaluation);
+        } else {
+            evaluation = evaluation.min(child_evaluation);
+            beta = beta.min(evaluation);
+        }
 
-    if depth == 0 || tree.state.is_terminal() {
-        tree.evaluation = tree.state.evaluate().unwrap_or(0.0);
-        return tree;
+        if alpha >= beta {
+            break;
+        }
     }
 
-    let actions = tree.state.get_possible_actions();
-    let mut next_states: Vec<_> = actions
-        .iter()
+    Some(Searched {
+        evaluation,
+        path_dependent,
+    })
+}
+
+/// `alpha_beta` using `make_move` and `unmake_move` instead of copying the
+/// position for every node. `state` is restored before returning, also
+/// when the deadline expires.
+pub fn alpha_beta_in_place<T: MakeMove, S: Table<T>>(
+    state: &mut T,
+    depth: u8,
+    maximizing_player: bool,
+    table: &mut S,
+    alpha: f32,
+    beta: f32,
+    deadline: Option<&Deadline>,
+) -> Option<f32> {
+    search_in_place(
+        state,
+        depth,
+        maximizing_player,
+        table,
+        alpha,
+        beta,
+        deadline,
+    )
+    .map(|searched| searched.evaluation)
+}
+
+/// `alpha_beta_in_place`, also telling whether the value relies on the
+/// path.
+fn search_in_place<T: MakeMove, S: Table<T>>(
+    state: &mut T,
+    depth: u8,
+    maximizing_player: bool,
+    table: &mut S,
+    mut alpha: f32,
+    mut beta: f32,
+    deadline: Option<&Deadline>,
+) -> Option<Searched> {
+    if deadline.is_some_and(Deadline::expired) {
+        return None;
+    }
+
+    if depth == 0 {
+        return quiescence_in_place(state, maximizing_player, alpha, beta, deadline);
+    }
+    if state.is_terminal() {
+        return Some(Searched::leaf(state));
+    }
+
+    let mut hash_action = None;
+    if let Some(entry) = table.probe(state) {
+        if entry.depth >= depth {
+            match entry.bound {
+                Bound::Exact => return Some(Searched::from(entry.evaluation)),
+                Bound::Lower => alpha = alpha.max(entry.evaluation),
+                Bound::Upper => beta = beta.min(entry.evaluation),
+            }
+            if alpha >= beta {
+                return Some(Searched::from(entry.evaluation));
+            }
+        }
+        hash_action = entry.best_action;
+    }
+
+    // Same move ordering as `alpha_beta`, each child is made once to score it
+    // and once more to search it.
+    let mut actions: Vec<_> = state
+        .get_possible_actions()
+        .into_iter()
         .map(|action| {
-            let state = tree.state.apply_action(action);
+            let undo = state.make_move(&action);
             let eval = state.evaluate().unwrap_or(0.0);
-            return (state, if maximizing_player { eval } else { -eval });
+            state.unmake_move(undo);
+            let score = if hash_action.as_ref() == Some(&action) {
+                f32::INFINITY
+            } else if maximizing_player {
+                eval
+            } else {
+                -eval
+            };
+            (action, score)
         })
         .collect();
-    next_states.sort_by(|a, b| {
+    actions.sort_by(|a, b| {
         a.1.partial_cmp(&b.1)
             .unwrap_or(std::cmp::Ordering::Equal)
             .reverse()
     });
 
-    for (new_state, _) in next_states {
-        if let Some(_) = explored_states.get(&new_state) {
-            tree.children.push(new_state);
-        } else {
-            let child_tree = minmax(
-                new_state,
+    let mut evaluation = if maximizing_player {
+        f32::NEG_INFINITY
+    } else {
+        f32::INFINITY
+    };
+    let mut best_action = None;
+    let mut path_dependent = false;
+    let (window_alpha, window_beta) = (alpha, beta);
+
+    for (action, _) in actions {
+        let undo = state.make_move(&action);
+        let child = match state.probe() {
+            Some(value) => Some(Searched::from(value)),
+            None => search_in_place(
+                state,
                 depth - 1,
                 !maximizing_player,
-                explored_states,
+                table,
                 alpha,
                 beta,
-            );
-            tree.children.push(child_tree.state.clone());
-            explored_states.insert(child_tree.state.clone(), child_tree);
+                deadline,
+            ),
+        };
+        state.unmake_move(undo);
+        let child = child?;
+        let child_evaluation = child.evaluation;
+        path_dependent |= child.path_dependent;
+
+        if maximizing_player {
+            if child_evaluation > evaluation || best_action.is_none() {
+                evaluation = child_evaluation;
+                best_action = Some(action);
+            }
+            alpha = alpha.max(evaluation);
+        } else {
+            if child_evaluation < evaluation || best_action.is_none() {
+                evaluation = child_evaluation;
+                best_action = Some(action);
+            }
+            beta = beta.min(evaluation);
         }
 
-        tree.evaluation = if maximizing_player {
-            tree.evaluation.max(
-                explored_states
-                    .get(tree.children.last().unwrap())
-                    .unwrap()
-                    .evaluation,
-            )
+        if alpha >= beta {
+            break; // alpha-beta cutoff
+        }
+    }
+
+    let bound = if evaluation <= window_alpha {
+        Bound::Upper
+    } else if evaluation >= window_beta {
+        Bound::Lower
+    } else {
+        Bound::Exact
+    };
+    let searched = Searched {
+        evaluation,
+        path_dependent,
+    };
+    store(table, state, depth, bound, searched, best_action);
+
+    Some(searched)
+}
+
+/// `quiescence` with in-place moves.
+fn quiescence_in_place<T: MakeMove>(
+    state: &mut T,
+    maximizing_player: bool,
+    mut alpha: f32,
+    mut beta: f32,
+    deadline: Option<&Deadline>,
+) -> Option<Searched> {
+    if deadline.is_some_and(Deadline::expired) {
+        return None;
+    }
+
+    if state.is_terminal() || state.is_quiet() {
+        return Some(Searched::leaf(state));
+    }
+
+    let mut evaluation = if maximizing_player {
+        f32::NEG_INFINITY
+    } else {
+        f32::INFINITY
+    };
+    let mut path_dependent = false;
+    for action in state.get_possible_actions() {
+        let undo = state.make_move(&action);
+        let child = quiescence_in_place(state, !maximizing_player, alpha, beta, deadline);
+        state.unmake_move(undo);
+        let child = child?;
+        let child_evaluation = child.evaluation;
+        path_dependent |= child.path_dependent;
+
+        if maximizing_player {
+            evaluation = evaluation.max(child_evaluation);
+            alpha = alpha.max(evaluation);
         } else {
-            tree.evaluation.min(
-                explored_states
-                    .get(tree.children.last().unwrap())
-                    .unwrap()
-                    .evaluation,
-            )
-        };
+            evaluation = evaluation.min(child_evaluation);
+            beta = beta.min(evaluation);
+        }
 
-        if tree.evaluation >= beta && maximizing_player {
-            break; // beta cutoff
-        } else if tree.evaluation <= alpha && !maximizing_player {
-            break; // alpha cutoff
+        if alpha >= beta {
+            break;
         }
+    }
+
+    Some(Searched {
+        evaluation,
+        path_dependent,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::four::{FourRow, GameAction};
+
+    fn play(columns: &[u8]) -> FourRow {
+        columns.iter().fold(FourRow::new(), |state, &column| {
+            state.apply_action(&GameAction { column })
+        })
+    }
+
+    /// Plays `plies` pseudo-random moves from the empty board.
+    fn random_position(seed: u64, plies: usize) -> FourRow {
+        let mut rng = seed;
+        let mut state = FourRow::new();
+        for _ in 0..plies {
+            let actions = state.get_possible_actions();
+            if state.is_terminal() || actions.is_empty() {
+                break;
+            }
+            rng = rng
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            state = state.apply_action(&actions[(rng >> 33) as usize % actions.len()]);
+        }
+        state
+    }
+
+    /// Full-width minimax without pruning or caching.
+    fn plain_minmax<T: GameState>(state: &T, depth: u8, maximizing_player: bool) -> f32 {
+        if depth == 0 || state.is_terminal() {
+            return state.evaluate().unwrap_or(0.0);
+        }
+        let values = state.get_possible_actions().into_iter().map(|action| {
+            plain_minmax(&state.apply_action(&action), depth - 1, !maximizing_player)
+        });
         if maximizing_player {
-            alpha = alpha.max(tree.evaluation);
+            values.fold(f32::NEG_INFINITY, f32::max)
         } else {
-            beta = beta.min(tree.evaluation);
+            values.fold(f32::INFINITY, f32::min)
         }
     }
 
-    return tree;
+    #[test]
+    fn test_transposition_table_matches_plain_minmax() {
+        for seed in 0..40 {
+            let state = random_position(seed, (seed % 20) as usize);
+            let maximizing_player = state.current_player == Player::Player1;
+            for depth in 1..=4 {
+                let mut table = TranspositionTable::new();
+                let result = minmax(
+                    &state,
+                    depth,
+                    maximizing_player,
+                    &mut table,
+                    f32::NEG_INFINITY,
+                    f32::INFINITY,
+                );
+                assert_eq!(
+                    result.evaluation,
+                    plain_minmax(&state, depth, maximizing_player),
+                    "seed {} depth {}:\n{:?}",
+                    seed,
+                    depth,
+                    state
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_transposition_table_keeps_deeper_entries() {
+        let entry = |depth, evaluation| TableEntry {
+            depth,
+            bound: Bound::Exact,
+            evaluation,
+            best_action: None,
+        };
+        let (first, second) = (FourRow::new(), play(&[3]));
+        let mut table = TranspositionTable::with_capacity(1);
+        table.insert(&first, entry(4, 1.0));
+        table.insert(&second, entry(2, 2.0));
+        assert_eq!(table.get(&first), Some(&entry(4, 1.0)));
+        assert_eq!(table.get(&second), None);
+
+        // The same position is always overwritten
+        table.insert(&first, entry(1, 3.0));
+        assert_eq!(table.get(&first), Some(&entry(1, 3.0)));
+        table.insert(&second, entry(1, 4.0));
+        assert_eq!(table.get(&first), None);
+        assert_eq!(table.get(&second), Some(&entry(1, 4.0)));
+    }
+
+    #[test]
+    fn test_small_transposition_table_matches_plain_minmax() {
+        for seed in 200..220 {
+            let state = random_position(seed, 4);
+            let maximizing_player = state.current_player == Player::Player1;
+            let mut table = TranspositionTable::with_capacity(13);
+            let result = minmax(
+                &state,
+                4,
+                maximizing_player,
+                &mut table,
+                f32::NEG_INFINITY,
+                f32::INFINITY,
+            );
+            assert_eq!(
+                result.evaluation,
+                plain_minmax(&state, 4, maximizing_player)
+            );
+        }
+    }
+
+    #[test]
+    fn test_transposition_table_reused_across_searches() {
+        // A table filled by shallower searches must not change deeper results.
+        for seed in 100..120 {
+            let state = random_position(seed, 6);
+            let maximizing_player = state.current_player == Player::Player1;
+            let mut table = TranspositionTable::new();
+            for depth in 1..=4 {
+                let result = minmax(
+                    &state,
+                    depth,
+                    maximizing_player,
+                    &mut table,
+                    f32::NEG_INFINITY,
+                    f32::INFINITY,
+                );
+                assert_eq!(
+                    result.evaluation,
+                    plain_minmax(&state, depth, maximizing_player)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_in_place_search_matches_copying_search() {
+        for seed in 200..230 {
+            let state = random_position(seed, (seed % 16) as usize);
+            let maximizing_player = state.current_player == Player::Player1;
+            let expected = minmax(
+                &state,
+                5,
+                maximizing_player,
+                &mut TranspositionTable::new(),
+                f32::NEG_INFINITY,
+                f32::INFINITY,
+            );
+            let result = minmax_in_place(
+                &state,
+                5,
+                maximizing_player,
+                &mut TranspositionTable::new(),
+                f32::NEG_INFINITY,
+                f32::INFINITY,
+            );
+            assert_eq!(result, expected, "seed {}:\n{:?}", seed, state);
+        }
+    }
+
+    #[test]
+    fn test_sliced_search_matches_iterative_deepening() {
+        let state = play(&[3, 3, 2, 4]);
+        let expected = iterative_deepening(&state, true, Duration::from_secs(60), 6);
+
+        let mut search = SlicedSearch::new(&state, true, 6);
+        assert_eq!(search.result(), None);
+        let mut completed = Vec::new();
+        while !search.step(Duration::from_millis(5)) {
+            if let Some(result) = search.result() {
+                completed.push(result.depth);
+            }
+        }
+        assert!(completed.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(search.result(), Some(&expected));
+        assert!(search.step(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_analyze_values_every_move() {
+        for (seed, depth) in [(300, 1), (301, 3), (302, 4)] {
+            let state = random_position(seed, 8);
+            let maximizing_player = state.current_player == Player::Player1;
+            let analysis = analyze(&state, depth, maximizing_player);
+            assert_eq!(analysis.len(), state.get_possible_actions().len());
+
+            for entry in &analysis {
+                let next_state = state.apply_action(&entry.action);
+                assert_eq!(
+                    entry.evaluation,
+                    plain_minmax(&next_state, depth - 1, !maximizing_player)
+                );
+                assert_eq!(entry.principal_variation[0], entry.action);
+            }
+            let sign = if maximizing_player { 1.0 } else { -1.0 };
+            assert!(
+                analysis
+                    .windows(2)
+                    .all(|pair| sign * pair[0].evaluation >= sign * pair[1].evaluation)
+            );
+            assert_eq!(
+                analysis[0].evaluation,
+                plain_minmax(&state, depth, maximizing_player)
+            );
+        }
+        assert!(analyze(&play(&[0, 1, 0, 1, 0, 1, 0]), 3, false).is_empty());
+    }
+
+    #[test]
+    fn test_search_result_reports_winning_action() {
+        let state = play(&[0, 0, 1, 1, 2, 2]);
+        let mut table = TranspositionTable::new();
+        let result = minmax(
+            &state,
+            4,
+            true,
+            &mut table,
+            f32::NEG_INFINITY,
+            f32::INFINITY,
+        );
+        assert_eq!(result.best_action, Some(GameAction { column: 3 }));
+        assert_eq!(result.principal_variation, vec![GameAction { column: 3 }]);
+        assert_eq!(result.evaluation, 100.0);
+        assert_eq!(result.depth, 4);
+    }
+
+    #[test]
+    fn test_principal_variation_is_playable() {
+        let state = play(&[3, 3, 2, 4]);
+        let mut table = TranspositionTable::new();
+        let result = minmax(
+            &state,
+            5,
+            true,
+            &mut table,
+            f32::NEG_INFINITY,
+            f32::INFINITY,
+        );
+        assert_eq!(result.principal_variation.len(), 5);
+        assert_eq!(
+            result.best_action.as_ref(),
+            result.principal_variation.first()
+        );
+        result
+            .principal_variation
+            .iter()
+            .fold(state, |position, action| {
+                assert!(position.get_possible_actions().contains(action));
+                position.apply_action(action)
+            });
+    }
+
+    #[test]
+    fn test_iterative_deepening_always_completes_depth_one() {
+        let result = iterative_deepening(&play(&[3, 3, 2]), false, Duration::ZERO, 10);
+        assert_eq!(result.depth, 1);
+        assert!(result.best_action.is_some());
+    }
+
+    #[test]
+    fn test_iterative_deepening_matches_fixed_depth() {
+        let state = play(&[3, 3, 2, 4]);
+        let result = iterative_deepening(&state, true, Duration::from_secs(60), 4);
+        assert_eq!(result.depth, 4);
+        assert_eq!(result.evaluation, plain_minmax(&state, 4, true));
+    }
 }
@@ -0,0 +1,331 @@
+//! Alpha-beta search on several threads by splitting the root moves
+//!
+//! Worker threads take the root moves one at a time, in the same order the
+//! sequential search would try them. They share a transposition table and
+//! the best root value found so far, which becomes the window bound for the
+//! root moves searched after it.
+//!
+//! Because of that sharing the chosen move among equally good ones, and the
+//! principal variation, depend on thread scheduling. In deterministic mode
+//! every root move is searched with a full window and a table of its own,
+//! so the result only depends on the position and depth, never on the
+//! number of threads.
+
+use std::{
+    hash::Hash,
+    sync::{
+        Mutex,
+        atomic::{AtomicU32, AtomicUsize, Ordering},
+    },
+    thread,
+};
+
+use crate::minmax::{
+    GameState, KeyMap, MakeMove, SearchResult, Table, TableEntry, TranspositionTable,
+    alpha_beta_in_place, principal_variation,
+};
+
+/// Number of independently locked parts of the shared table.
+const SHARDS: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParallelConfig {
+    /// Number of worker threads, at least one is always used.
+    pub threads: usize,
+    /// Search every root move independently so results are reproducible.
+    pub deterministic: bool,
+}
+
+impl Default for ParallelConfig {
+    fn default() -> Self {
+        ParallelConfig {
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            deterministic: false,
+        }
+    }
+}
+
+/// Transposition table shared by the worker threads, split into shards by
+/// the high bits of the position key to keep lock contention low.
+struct SharedTable<T: GameState> {
+    shards: Vec<Mutex<KeyMap<TableEntry<T::Action>>>>,
+}
+
+impl<T: GameState> SharedTable<T> {
+    fn new() -> Self {
+        SharedTable {
+            shards: (0..SHARDS).map(|_| Mutex::new(KeyMap::default())).collect(),
+        }
+    }
+
+    fn shard(&self, key: u64) -> &Mutex<KeyMap<TableEntry<T::Action>>> {
+        &self.shards[(key >> 32) as usize % SHARDS]
+    }
+}
+
+impl<T: GameState> Table<T> for &SharedTable<T> {
+    fn probe(&self, state: &T) -> Option<TableEntry<T::Action>> {
+        let key = state.hash_key();
+        self.shard(key)
+            .lock()
+            .expect("table lock poisoned")
+            .get(&key)
+            .cloned()
+    }
+
+    fn store(&mut self, state: &T, entry: TableEntry<T::Action>) {
+        let key = state.hash_key();
+        self.shard(key)
+            .lock()
+            .expect("table lock poisoned")
+            .insert(key, entry);
+    }
+}
+
+/// Best root value so far from the side to move, stored as `f32` bits.
+struct SharedBound(AtomicU32);
+
+impl SharedBound {
+    fn new() -> Self {
+        SharedBound(AtomicU32::new(f32::NEG_INFINITY.to_bits()))
+    }
+
+    fn get(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Acquire))
+    }
+
+    fn raise(&self, score: f32) {
+        let _ = self
+            .0
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |bits| {
+                (score > f32::from_bits(bits)).then_some(score.to_bits())
+            });
+    }
+}
+
+/// Result of searching one root move.
+struct RootMove<A> {
+    /// Value from the point of view of the side to move at the root.
+    score: f32,
+    /// False if the move failed low against the shared bound, in which case
+    /// `score` is only an upper bound and the move cannot be the best.
+    exact: bool,
+    /// Principal variation after the root move.
+    line: Vec<A>,
+}
+
+/// Searches `state` to `depth` like `minmax` with a full window, using
+/// `config.threads` threads.
+///
+/// `maximizing_player` is true if Player1 is to move.
+pub fn parallel_minmax<T>(
+    state: &T,
+    depth: u8,
+    maximizing_player: bool,
+    config: &ParallelConfig,
+) -> SearchResult<T::Action>
+where
+    T: MakeMove + Hash + Eq + Clone + Send + Sync,
+    T::Action: Send + Sync,
+{
+    if depth == 0 || state.is_terminal() {
+        return SearchResult {
+            best_action: None,
+            evaluation: state.evaluate().unwrap_or(0.0),
+            principal_variation: Vec::new(),
+            depth,
+        };
+    }
+
+    let sign = if maximizing_player { 1.0 } else { -1.0 };
+    let mut root_moves: Vec<_> = state
+        .get_possible_actions()
+        .into_iter()
+        .map(|action| {
+            let next_state = state.apply_action(&action);
+            let score = sign * next_state.evaluate().unwrap_or(0.0);
+            (action, next_state, score)
+        })
+        .collect();
+    root_moves.sort_by(|a, b| b.2.total_cmp(&a.2));
+
+    let table = SharedTable::new();
+    let bound = SharedBound::new();
+    let next = AtomicUsize::new(0);
+
+    let search_root_moves = || {
+        let mut searched = Vec::new();
+        loop {
+            let index = next.fetch_add(1, Ordering::Relaxed);
+            let Some((_, next_state, _)) = root_moves.get(index) else {
+                break;
+            };
+            let best = if config.deterministic {
+                f32::NEG_INFINITY
+            } else {
+                bound.get()
+            };
+            let (alpha, beta) = if maximizing_player {
+                (best, f32::INFINITY)
+            } else {
+                (f32::NEG_INFINITY, -best)
+            };
+
+            let (evaluation, line) = if config.deterministic {
+                let mut own_table = TranspositionTable::new();
+                let evaluation = alpha_beta_in_place(
+                    &mut next_state.clone(),
+                    depth - 1,
+                    !maximizing_player,
+                    &mut own_table,
+                    alpha,
+                    beta,
+                    None,
+                );
+                (
+                    evaluation,
+                    principal_variation(next_state, depth - 1, &own_table),
+                )
+            } else {
+                let mut shared = &table;
+                let evaluation = alpha_beta_in_place(
+                    &mut next_state.clone(),
+                    depth - 1,
+                    !maximizing_player,
+                    &mut shared,
+                    alpha,
+                    beta,
+                    None,
+                );
+                (
+                    evaluation,
+                    principal_variation(next_state, depth - 1, &shared),
+                )
+            };
+            let score = sign * evaluation.expect("search without a deadline always completes");
+
+            let exact = best == f32::NEG_INFINITY || score > best;
+            if exact {
+                bound.raise(score);
+            }
+            searched.push((index, RootMove { score, exact, line }));
+        }
+        searched
+    };
+
+    let mut searched: Vec<_> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..config.threads.max(1))
+            .map(|_| scope.spawn(search_root_moves))
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("search thread panicked"))
+            .collect()
+    });
+
+    // Ties go to the move tried first, as in the sequential search
+    searched.sort_by_key(|(index, _)| *index);
+    let mut best: Option<&(usize, RootMove<T::Action>)> = None;
+    for candidate in searched.iter().filter(|(_, result)| result.exact) {
+        if best.is_none_or(|(_, best)| candidate.1.score > best.score) {
+            best = Some(candidate);
+        }
+    }
+    let (index, result) = best.expect("the best root move is always exact");
+
+    let best_action = root_moves[*index].0.clone();
+    let mut principal_variation = vec![best_action.clone()];
+    principal_variation.extend(result.line.iter().cloned());
+    SearchResult {
+        best_action: Some(best_action),
+        evaluation: sign * result.score,
+        principal_variation,
+        depth,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::four::{FourRow, GameAction};
+    use crate::minmax::{Player, minmax};
+
+    fn deterministic(threads: usize) -> ParallelConfig {
+        ParallelConfig {
+            threads,
+            deterministic: true,
+        }
+    }
+
+    fn play(moves: &str) -> FourRow {
+        FourRow::from_moves(moves).unwrap()
+    }
+
+    fn sequential(state: &FourRow, depth: u8) -> SearchResult<GameAction> {
+        let mut table = TranspositionTable::new();
+        minmax(
+            state,
+            depth,
+            state.current_player == Player::Player1,
+            &mut table,
+            f32::NEG_INFINITY,
+            f32::INFINITY,
+        )
+    }
+
+    #[test]
+    fn test_matches_sequential_evaluation() {
+        for moves in ["", "4", "4453", "33443", "1234567"] {
+            let state = play(moves);
+            let maximizing_player = state.current_player == Player::Player1;
+            let expected = sequential(&state, 6).evaluation;
+            for config in [
+                deterministic(4),
+                ParallelConfig {
+                    threads: 4,
+                    deterministic: false,
+                },
+            ] {
+                let result = parallel_minmax(&state, 6, maximizing_player, &config);
+                assert_eq!(result.evaluation, expected, "moves {moves:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_deterministic_mode_ignores_thread_count() {
+        let state = play("43");
+        let single = parallel_minmax(&state, 7, true, &deterministic(1));
+        for threads in [2, 3, 8] {
+            assert_eq!(
+                parallel_minmax(&state, 7, true, &deterministic(threads)),
+                single
+            );
+        }
+    }
+
+    #[test]
+    fn test_principal_variation_is_playable() {
+        let state = play("4453");
+        let result = parallel_minmax(&state, 5, true, &ParallelConfig::default());
+        assert_eq!(
+            result.best_action.as_ref(),
+            result.principal_variation.first()
+        );
+        result
+            .principal_variation
+            .iter()
+            .fold(state, |position, action| {
+                assert!(position.get_possible_actions().contains(action));
+                position.apply_action(action)
+            });
+    }
+
+    #[test]
+    fn test_terminal_position() {
+        let state = play("1212121");
+        let result = parallel_minmax(&state, 4, false, &ParallelConfig::default());
+        assert_eq!(result.best_action, None);
+        assert_eq!(result.evaluation, state.evaluate().unwrap());
+    }
+}
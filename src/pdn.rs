@@ -0,0 +1,845 @@
+//! Portable Draughts Notation (PDN) for checkers games
+//!
+//! Squares use the English draughts numbering from 1 to 32. Player1 plays
+//! the black pieces, starts on squares 1-12 and moves first; Player2 plays
+//! white and starts on squares 21-32. Square 1 is `board[0][6]` and square
+//! 32 is `board[7][1]` of a `CheckersBoard`.
+//!
+//! Moves are written as the squares a piece visits, separated by `-` for a
+//! plain move and `x` for a capture, e.g. `11-15` or `22x15x8`. When reading,
+//! a capture may also be given by its first and last square only as long as
+//! that picks out a single legal move.
+//!
+//! Positions are written in draughts FEN, e.g. `W:W21,22,K5:B1,2`: the side
+//! to move, then the white and the black pieces with `K` marking kings.
+//! `moves_without_capture` is added as an `H` field (`W:W21:B1:H12`) when it
+//! is not zero.
+//!
+//! The `GameType` header of a game selects its rules: 21 for English and 23
+//! for pool checkers. Other variants have no game type here: Italian,
+//! Russian and Brazilian PDN let White move first, and the last two also
+//! name squares algebraically, so their games cannot be written in this
+//! notation.
+
+use std::fmt::Display;
+
+use crate::checkers::{CheckersBoard, CheckersTile, RulesConfig};
+use crate::minmax::{GameState, Player};
+
+type Action = <CheckersBoard as GameState>::Action;
+
+/// Row and column on `CheckersBoard::board` of a square from 1 to 32.
+pub fn square_to_position(square: u8) -> Option<(usize, usize)> {
+    if !(1..=32).contains(&square) {
+        return None;
+    }
+    let index = (square - 1) as usize;
+    let row = index / 4;
+    let file = 2 * (index % 4) + (1 - row % 2);
+    Some((row, 7 - file))
+}
+
+/// Square number of a board position, or `None` for a light square.
+pub fn position_to_square(row: usize, col: usize) -> Option<u8> {
+    if row >= 8 || col >= 8 || (row + col) % 2 == 1 {
+        return None;
+    }
+    Some((row * 4 + (7 - col) / 2 + 1) as u8)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PdnError {
+    /// A header line that is not of the form `[Name "value"]`.
+    InvalidHeader { line: String },
+    /// A `{` comment or `(` variation that is never closed.
+    UnterminatedComment,
+    /// The `FEN` header does not describe a position.
+    InvalidFen(FenError),
+    /// A move that is not a list of squares from 1 to 32.
+    InvalidMove { ply: usize, text: String },
+    /// A well-formed move that cannot be played in the position.
+    IllegalMove { ply: usize, text: String },
+    /// A move that matches more than one legal move.
+    AmbiguousMove {
+        ply: usize,
+        text: String,
+        candidates: Vec<String>,
+    },
+    /// A `GameType` header naming rules other than the supported ones.
+    UnsupportedGameType { game_type: String },
+}
+
+impl Display for PdnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PdnError::InvalidHeader { line } => write!(f, "invalid header line '{}'", line),
+            PdnError::UnterminatedComment => write!(f, "unterminated comment or variation"),
+            PdnError::InvalidFen(err) => write!(f, "invalid FEN header: {}", err),
+            PdnError::InvalidMove { ply, text } => {
+                write!(f, "'{}' at ply {} is not a move", text, ply)
+            }
+            PdnError::IllegalMove { ply, text } => {
+                write!(f, "illegal move '{}' at ply {}", text, ply)
+            }
+            PdnError::AmbiguousMove {
+                ply,
+                text,
+                candidates,
+            } => write!(
+                f,
+                "ambiguous move '{}' at ply {}, could be any of {}",
+                text,
+                ply,
+                candidates.join(", ")
+            ),
+            PdnError::UnsupportedGameType { game_type } => {
+                write!(f, "unsupported game type '{}'", game_type)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PdnError {}
+
+/// Squares visited by an action, starting with the square the piece leaves.
+fn action_squares(action: &Action) -> Vec<u8> {
+    let mut squares = Vec::with_capacity(action.len() + 1);
+    if let Some(&(from_row, from_col, _, _)) = action.first() {
+        squares.extend(position_to_square(from_row, from_col));
+    }
+    for &(_, _, to_row, to_col) in action {
+        squares.extend(position_to_square(to_row, to_col));
+    }
+    squares
+}
+
+/// Writes an action in PDN, e.g. `11-15` or `22x15x8`. `state` is the
+/// position it is played in, which tells a capture by a flying king from a
+/// plain move.
+pub fn format_move(state: &CheckersBoard, action: &Action) -> String {
+    let separator = if state.is_capture(action) { "x" } else { "-" };
+    action_squares(action)
+        .iter()
+        .map(|square| square.to_string())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// Finds the legal action that a PDN move stands for. `ply` is only used
+/// in error messages.
+pub fn parse_move(state: &CheckersBoard, text: &str, ply: usize) -> Result<Action, PdnError> {
+    let squares = text
+        .split(['-', 'x'])
+        .map(|part| {
+            part.parse::<u8>()
+                .ok()
+                .filter(|square| (1..=32).contains(square))
+        })
+        .collect::<Option<Vec<_>>>()
+        .filter(|squares| squares.len() >= 2)
+        .ok_or_else(|| PdnError::InvalidMove {
+            ply,
+            text: text.to_string(),
+        })?;
+
+    let mut candidates = state.get_possible_actions().into_iter().filter(|action| {
+        let path = action_squares(action);
+        if squares.len() > 2 {
+            path == squares
+        } else {
+            path.first() == squares.first() && path.last() == squares.last()
+        }
+    });
+
+    match (candidates.next(), candidates.next()) {
+        (Some(action), None) => Ok(action),
+        (None, _) => Err(PdnError::IllegalMove {
+            ply,
+            text: text.to_string(),
+        }),
+        (Some(first), Some(second)) => Err(PdnError::AmbiguousMove {
+            ply,
+            text: text.to_string(),
+            candidates: [first, second]
+                .iter()
+                .chain(candidates.collect::<Vec<_>>().iter())
+                .map(|action| format_move(state, action))
+                .collect(),
+        }),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FenError {
+    /// Not of the form `turn:pieces:pieces` with an optional `:H` field.
+    InvalidLayout,
+    /// A side other than `W` or `B`.
+    InvalidSide { text: String },
+    /// A piece that is not a square from 1 to 32 or a range of them.
+    InvalidSquare { text: String },
+    /// The same square is given twice.
+    DuplicateSquare { square: u8 },
+    /// A man on the row where it would have been crowned.
+    UncrownedMan { square: u8 },
+    /// An `H` field that is not a number.
+    InvalidCounter { text: String },
+}
+
+impl Display for FenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FenError::InvalidLayout => {
+                write!(f, "expected the side to move and the pieces of both sides")
+            }
+            FenError::InvalidSide { text } => write!(f, "'{}' is not W or B", text),
+            FenError::InvalidSquare { text } => write!(f, "'{}' is not a square", text),
+            FenError::DuplicateSquare { square } => {
+                write!(f, "square {} is given more than once", square)
+            }
+            FenError::UncrownedMan { square } => {
+                write!(f, "the man on square {} should be a king", square)
+            }
+            FenError::InvalidCounter { text } => {
+                write!(f, "'{}' is not a moves without capture count", text)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+fn parse_side(text: &str) -> Result<Player, FenError> {
+    match text {
+        "B" => Ok(Player::Player1),
+        "W" => Ok(Player::Player2),
+        _ => Err(FenError::InvalidSide {
+            text: text.to_string(),
+        }),
+    }
+}
+
+fn side_letter(player: Player) -> char {
+    match player {
+        Player::Player1 => 'B',
+        Player::Player2 => 'W',
+    }
+}
+
+/// Reads a position in draughts FEN. Squares may also be given as ranges,
+/// e.g. `B:W21-32:B1-12` for the starting position.
+pub fn parse_fen(fen: &str) -> Result<CheckersBoard, FenError> {
+    let fields: Vec<&str> = fen.trim().trim_end_matches('.').split(':').collect();
+    if !(3..=4).contains(&fields.len()) {
+        return Err(FenError::InvalidLayout);
+    }
+
+    let current_player = parse_side(fields[0].trim())?;
+    let mut board = [[CheckersTile::Empty; 8]; 8];
+
+    let mut sides = Vec::new();
+    for field in &fields[1..3] {
+        let field = field.trim();
+        let split = field.chars().next().map_or(0, char::len_utf8);
+        let player = parse_side(&field[..split])?;
+        if sides.contains(&player) {
+            return Err(FenError::InvalidLayout);
+        }
+        sides.push(player);
+
+        for piece in field[split..].split(',').map(str::trim) {
+            if piece.is_empty() {
+                continue;
+            }
+            let (tile, squares) = match piece.strip_prefix('K') {
+                Some(squares) => (CheckersTile::Queen(player), squares),
+                None => (CheckersTile::Checker(player), piece),
+            };
+            let invalid = || FenError::InvalidSquare {
+                text: piece.to_string(),
+            };
+            let (first, last) = squares.split_once('-').unwrap_or((squares, squares));
+            let first = first.parse::<u8>().map_err(|_| invalid())?;
+            let last = last.parse::<u8>().map_err(|_| invalid())?;
+            if first > last {
+                return Err(invalid());
+            }
+
+            for square in first..=last {
+                let (row, col) = square_to_position(square).ok_or_else(invalid)?;
+                if board[row][col] != CheckersTile::Empty {
+                    return Err(FenError::DuplicateSquare { square });
+                }
+                if tile == CheckersTile::Checker(player)
+                    && row == CheckersBoard::crowning_row(player)
+                {
+                    return Err(FenError::UncrownedMan { square });
+                }
+                board[row][col] = tile;
+            }
+        }
+    }
+
+    let mut moves_without_capture = 0;
+    if let Some(field) = fields.get(3) {
+        let field = field.trim();
+        moves_without_capture = field
+            .strip_prefix('H')
+            .and_then(|count| count.parse().ok())
+            .ok_or_else(|| FenError::InvalidCounter {
+                text: field.to_string(),
+            })?;
+    }
+    Ok(CheckersBoard::from_board(
+        board,
+        current_player,
+        moves_without_capture,
+    ))
+}
+
+/// Writes a position in draughts FEN, listing men before kings.
+pub fn to_fen(state: &CheckersBoard) -> String {
+    let mut fen = side_letter(state.current_player()).to_string();
+    for player in [Player::Player2, Player::Player1] {
+        let mut men = Vec::new();
+        let mut kings = Vec::new();
+        for square in 1..=32 {
+            let (row, col) = square_to_position(square).unwrap();
+            match state.board()[row][col] {
+                CheckersTile::Checker(p) if p == player => men.push(square.to_string()),
+                CheckersTile::Queen(p) if p == player => kings.push(format!("K{}", square)),
+                _ => {}
+            }
+        }
+        men.extend(kings);
+        fen.push_str(&format!(":{}{}", side_letter(player), men.join(",")));
+    }
+    if state.moves_without_capture > 0 {
+        fen.push_str(&format!(":H{}", state.moves_without_capture));
+    }
+    fen
+}
+
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/// `GameType` header values of the rules PDN games can be played by.
+const GAME_TYPES: [(&str, RulesConfig); 2] =
+    [("21", RulesConfig::ENGLISH), ("23", RulesConfig::POOL)];
+
+/// `GameType` header value of `rules`, `None` if PDN games cannot be
+/// played by them.
+pub fn game_type(rules: RulesConfig) -> Option<&'static str> {
+    GAME_TYPES
+        .iter()
+        .find(|(_, game_rules)| *game_rules == rules)
+        .map(|(game_type, _)| *game_type)
+}
+
+/// PDN result of a position: `1-0` if Player1 won, `0-1` if Player2 won,
+/// `1/2-1/2` for a draw and `*` while the game goes on.
+fn result_tag(state: &CheckersBoard) -> &'static str {
+    if !state.is_terminal() {
+        "*"
+    } else if state.count_pieces(Player::Player1) == 0 {
+        "0-1"
+    } else if state.count_pieces(Player::Player2) == 0 {
+        "1-0"
+    } else {
+        "1/2-1/2"
+    }
+}
+
+/// A game read from or written to PDN. `start` is the position given by
+/// the `FEN` header, or the usual starting position without one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PdnGame {
+    pub headers: Vec<(String, String)>,
+    pub start: CheckersBoard,
+    pub moves: Vec<Action>,
+}
+
+impl PdnGame {
+    /// A game of the given moves, with the `GameType` and `Result` headers
+    /// filled in and `SetUp` and `FEN` if it does not begin at the usual
+    /// starting position. Rules without a game type leave out `GameType`.
+    pub fn new(start: CheckersBoard, moves: Vec<Action>) -> Self {
+        let mut headers = Vec::new();
+        if let Some(game_type) = game_type(start.rules()) {
+            headers.push(("GameType".to_string(), game_type.to_string()));
+        }
+        if start != CheckersBoard::new().with_rules(start.rules()) {
+            headers.push(("SetUp".to_string(), "1".to_string()));
+            headers.push(("FEN".to_string(), to_fen(&start)));
+        }
+        let mut game = PdnGame {
+            headers,
+            start,
+            moves,
+        };
+        let result = result_tag(&game.final_position()).to_string();
+        game.headers.push(("Result".to_string(), result));
+        game
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn final_position(&self) -> CheckersBoard {
+        self.moves.iter().fold(self.start.clone(), |state, action| {
+            state.apply_action(action)
+        })
+    }
+
+    /// Reads a single game. Comments, variations, move numbers and
+    /// annotation marks like `!` are skipped.
+    pub fn parse(text: &str) -> Result<Self, PdnError> {
+        let mut headers = Vec::new();
+        let mut movetext = String::new();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.starts_with('[') && movetext.trim().is_empty() {
+                headers.push(parse_header(line)?);
+            } else {
+                movetext.push_str(line);
+                movetext.push('\n');
+            }
+        }
+
+        // Only the first field of e.g. `20,W,10,10,N2,0` names the rules
+        let rules = match headers.iter().find(|(name, _)| name == "GameType") {
+            Some((_, game_type)) => {
+                let number = game_type.split(',').next().unwrap_or_default().trim();
+                GAME_TYPES
+                    .iter()
+                    .find(|(known, _)| *known == number)
+                    .map(|&(_, rules)| rules)
+                    .ok_or_else(|| PdnError::UnsupportedGameType {
+                        game_type: game_type.clone(),
+                    })?
+            }
+            None => RulesConfig::ENGLISH,
+        };
+        let start = match headers.iter().find(|(name, _)| name == "FEN") {
+            Some((_, fen)) => parse_fen(fen).map_err(PdnError::InvalidFen)?,
+            None => CheckersBoard::new(),
+        }
+        .with_rules(rules);
+
+        let mut state = start.clone();
+        let mut moves = Vec::new();
+        for token in movetext_tokens(&movetext)? {
+            if RESULTS.contains(&token.as_str()) {
+                break;
+            }
+            // Drop move numbers such as `12.` or `12...`, also when glued
+            // to the move itself as in `1.11-15`
+            let text = token.rsplit('.').next().unwrap_or_default();
+            let text = text.trim_end_matches(['!', '?']);
+            if text.is_empty() {
+                continue;
+            }
+
+            let action = parse_move(&state, text, moves.len() + 1)?;
+            state = state.apply_action(&action);
+            moves.push(action);
+        }
+
+        Ok(PdnGame {
+            headers,
+            start,
+            moves,
+        })
+    }
+
+    /// Writes the game with one header per line followed by the movetext
+    /// and the result.
+    pub fn to_pdn(&self) -> String {
+        let mut pdn = String::new();
+        for (name, value) in &self.headers {
+            pdn.push_str(&format!("[{} \"{}\"]\n", name, value));
+        }
+        if !self.headers.is_empty() {
+            pdn.push('\n');
+        }
+
+        // Games set up with White to move start with `1...`
+        let offset = (self.start.current_player() == Player::Player2) as usize;
+        let mut movetext = Vec::new();
+        let mut state = self.start.clone();
+        for (ply, action) in self.moves.iter().enumerate() {
+            if (ply + offset).is_multiple_of(2) {
+                movetext.push(format!("{}.", (ply + offset) / 2 + 1));
+            } else if ply == 0 {
+                movetext.push("1...".to_string());
+            }
+            movetext.push(format_move(&state, action));
+            state = state.apply_action(action);
+        }
+        movetext.push(self.header("Result").unwrap_or("*").to_string());
+        pdn.push_str(&movetext.join(" "));
+        pdn.push('\n');
+        pdn
+    }
+}
+
+fn parse_header(line: &str) -> Result<(String, String), PdnError> {
+    let invalid = || PdnError::InvalidHeader {
+        line: line.to_string(),
+    };
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .ok_or_else(invalid)?;
+    let (name, value) = inner
+        .trim()
+        .split_once(char::is_whitespace)
+        .ok_or_else(invalid)?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+        .ok_or_else(invalid)?;
+    Ok((name.to_string(), value.to_string()))
+}
+
+/// Splits movetext into whitespace separated tokens, leaving out `{...}`
+/// comments, `(...)` variations and `;` comments to the end of the line.
+fn movetext_tokens(movetext: &str) -> Result<Vec<String>, PdnError> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut chars = movetext.chars();
+    while let Some(character) = chars.next() {
+        match character {
+            '{' => {
+                chars
+                    .by_ref()
+                    .find(|&c| c == '}')
+                    .ok_or(PdnError::UnterminatedComment)?;
+            }
+            '(' => {
+                let mut depth = 1;
+                while depth > 0 {
+                    match chars.next().ok_or(PdnError::UnterminatedComment)? {
+                        '(' => depth += 1,
+                        ')' => depth -= 1,
+                        _ => {}
+                    }
+                }
+            }
+            ';' => {
+                chars.by_ref().find(|&c| c == '\n');
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                token.push(c);
+                continue;
+            }
+        }
+        if !token.is_empty() {
+            tokens.push(std::mem::take(&mut token));
+        }
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn actions(start: &CheckersBoard, moves: &[&str]) -> Vec<Action> {
+        moves
+            .iter()
+            .enumerate()
+            .scan(start.clone(), |state, (ply, text)| {
+                let action = parse_move(state, text, ply + 1).unwrap();
+                *state = state.apply_action(&action);
+                Some(action)
+            })
+            .collect()
+    }
+
+    fn play(moves: &[&str]) -> CheckersBoard {
+        moves
+            .iter()
+            .enumerate()
+            .fold(CheckersBoard::new(), |state, (ply, text)| {
+                let action = parse_move(&state, text, ply + 1).unwrap();
+                state.apply_action(&action)
+            })
+    }
+
+    #[test]
+    fn test_square_numbering() {
+        for square in 1..=32 {
+            let (row, col) = square_to_position(square).unwrap();
+            assert_eq!(position_to_square(row, col), Some(square));
+        }
+        assert_eq!(square_to_position(0), None);
+        assert_eq!(square_to_position(33), None);
+        assert_eq!(position_to_square(0, 1), None);
+
+        let start = CheckersBoard::new();
+        for square in 1..=12 {
+            let (row, col) = square_to_position(square).unwrap();
+            assert_eq!(
+                start.board()[row][col],
+                CheckersTile::Checker(Player::Player1)
+            );
+        }
+        for square in 21..=32 {
+            let (row, col) = square_to_position(square).unwrap();
+            assert_eq!(
+                start.board()[row][col],
+                CheckersTile::Checker(Player::Player2)
+            );
+        }
+    }
+
+    #[test]
+    fn test_opening_moves() {
+        let mut opening: Vec<String> = CheckersBoard::new()
+            .get_possible_actions()
+            .iter()
+            .map(|action| format_move(&CheckersBoard::new(), action))
+            .collect();
+        opening.sort();
+        assert_eq!(
+            opening,
+            ["10-14", "10-15", "11-15", "11-16", "12-16", "9-13", "9-14"]
+        );
+    }
+
+    #[test]
+    fn test_parse_capture() {
+        let state = play(&["11-15", "23-19", "8-11", "22-17", "9-13", "17-14", "10x17"]);
+        let actions = state.get_possible_actions();
+        assert_eq!(actions.len(), 2);
+        for action in &actions {
+            assert!(state.is_capture(action));
+            assert_eq!(
+                parse_move(&state, &format_move(&state, action), 8).as_ref(),
+                Ok(action)
+            );
+        }
+        // Captures are mandatory
+        assert_eq!(
+            parse_move(&state, "24-20", 8),
+            Err(PdnError::IllegalMove {
+                ply: 8,
+                text: "24-20".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let state = CheckersBoard::new();
+        assert_eq!(
+            parse_move(&state, "11-40", 1),
+            Err(PdnError::InvalidMove {
+                ply: 1,
+                text: "11-40".to_string()
+            })
+        );
+        assert_eq!(
+            parse_move(&state, "11", 1),
+            Err(PdnError::InvalidMove {
+                ply: 1,
+                text: "11".to_string()
+            })
+        );
+        assert_eq!(
+            parse_move(&state, "11-14", 1),
+            Err(PdnError::IllegalMove {
+                ply: 1,
+                text: "11-14".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_ambiguous_capture() {
+        // A king on 7 can take 10, 11, 18 and 19 in either direction and
+        // ends up back on 7 both times
+        let state = parse_fen("B:W10,11,18,19:BK7").unwrap();
+
+        assert_eq!(
+            parse_move(&state, "7x7", 1),
+            Err(PdnError::AmbiguousMove {
+                ply: 1,
+                text: "7x7".to_string(),
+                candidates: vec!["7x16x23x14x7".to_string(), "7x14x23x16x7".to_string()]
+            })
+        );
+        assert!(parse_move(&state, "7x16x23x14x7", 1).is_ok());
+    }
+
+    #[test]
+    fn test_parse_game() {
+        let pdn = r#"[Event "Club match"]
+[Black "Alice"]
+[White "Bob"]
+[Result "*"]
+
+1. 11-15 {a quiet start} 23-19 2. 8-11 22-17
+3.9-13 (3. 11-16 24x15) 17-14 4. 10x17! ; forced
+4... 21x14 *"#;
+        let game = PdnGame::parse(pdn).unwrap();
+        assert_eq!(game.header("Black"), Some("Alice"));
+        assert_eq!(game.header("Result"), Some("*"));
+        assert_eq!(game.moves.len(), 8);
+        let before = play(&["11-15", "23-19", "8-11", "22-17", "9-13", "17-14"]);
+        assert_eq!(format_move(&before, &game.moves[6]), "10x17");
+        assert_eq!(
+            game.final_position(),
+            play(&[
+                "11-15", "23-19", "8-11", "22-17", "9-13", "17-14", "10x17", "21x14"
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_game_errors() {
+        assert_eq!(
+            PdnGame::parse("[Event Club match]\n1. 11-15"),
+            Err(PdnError::InvalidHeader {
+                line: "[Event Club match]".to_string()
+            })
+        );
+        assert_eq!(
+            PdnGame::parse("1. 11-15 {comment"),
+            Err(PdnError::UnterminatedComment)
+        );
+        assert_eq!(
+            PdnGame::parse("1. 11-15 23-19 2. 15-19"),
+            Err(PdnError::IllegalMove {
+                ply: 3,
+                text: "15-19".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let start = CheckersBoard::new();
+        let moves = ["11-15", "23-19", "8-11", "22-17", "9-13", "17-14", "10x17"];
+        let game = PdnGame::new(start.clone(), actions(&start, &moves));
+        let pdn = game.to_pdn();
+        assert_eq!(
+            pdn,
+            "[GameType \"21\"]\n[Result \"*\"]\n\n\
+             1. 11-15 23-19 2. 8-11 22-17 3. 9-13 17-14 4. 10x17 *\n"
+        );
+        assert_eq!(PdnGame::parse(&pdn), Ok(game));
+    }
+
+    #[test]
+    fn test_export_set_up_position() {
+        let start = parse_fen("W:W21,22,K5:B1,2").unwrap();
+        let game = PdnGame::new(start.clone(), actions(&start, &["5-9", "1-6"]));
+        let pdn = game.to_pdn();
+        assert_eq!(
+            pdn,
+            "[GameType \"21\"]\n[SetUp \"1\"]\n[FEN \"W:W21,22,K5:B1,2\"]\n\
+             [Result \"*\"]\n\n1... 5-9 2. 1-6 *\n"
+        );
+        assert_eq!(PdnGame::parse(&pdn), Ok(game));
+
+        assert!(matches!(
+            PdnGame::parse("[FEN \"W:W21\"]\n*"),
+            Err(PdnError::InvalidFen(FenError::InvalidLayout))
+        ));
+    }
+
+    #[test]
+    fn test_game_type() {
+        // A pool king flies from 32 to 5 and back
+        let start = parse_fen("W:WK32:B1")
+            .unwrap()
+            .with_rules(RulesConfig::POOL);
+        let game = PdnGame::new(start.clone(), actions(&start, &["32-5", "1-6", "5-32"]));
+        let pdn = game.to_pdn();
+        assert_eq!(
+            pdn,
+            "[GameType \"23\"]\n[SetUp \"1\"]\n[FEN \"W:WK32:B1\"]\n\
+             [Result \"*\"]\n\n1... 32-5 2. 1-6 5-32 *\n"
+        );
+        assert_eq!(PdnGame::parse(&pdn), Ok(game));
+
+        assert_eq!(game_type(RulesConfig::RUSSIAN), None);
+        for game_type in ["20,W,10,10,N2,0", "22", "25", "26"] {
+            assert_eq!(
+                PdnGame::parse(&format!("[GameType \"{}\"]\n*", game_type)),
+                Err(PdnError::UnsupportedGameType {
+                    game_type: game_type.to_string()
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_fen() {
+        let state = parse_fen("W:W21,22,K5:B1,2").unwrap();
+        assert_eq!(state.current_player(), Player::Player2);
+        assert_eq!(state.count_pieces(Player::Player1), 2);
+        assert_eq!(state.count_pieces(Player::Player2), 3);
+        let (row, col) = square_to_position(5).unwrap();
+        assert_eq!(
+            state.board()[row][col],
+            CheckersTile::Queen(Player::Player2)
+        );
+        assert_eq!(to_fen(&state), "W:W21,22,K5:B1,2");
+
+        assert_eq!(parse_fen("B:W21-32:B1-12"), Ok(CheckersBoard::new()));
+        assert_eq!(
+            parse_fen(&to_fen(&CheckersBoard::new())),
+            Ok(CheckersBoard::new())
+        );
+
+        let state = parse_fen("B:W18:BK3:H12").unwrap();
+        assert_eq!(state.moves_without_capture, 12);
+        assert_eq!(to_fen(&state), "B:W18:BK3:H12");
+    }
+
+    #[test]
+    fn test_fen_errors() {
+        assert_eq!(parse_fen("W:W21"), Err(FenError::InvalidLayout));
+        assert_eq!(parse_fen("W:W21:W1"), Err(FenError::InvalidLayout));
+        assert_eq!(
+            parse_fen("R:W21:B1"),
+            Err(FenError::InvalidSide {
+                text: "R".to_string()
+            })
+        );
+        assert_eq!(
+            parse_fen("W:W21:B33"),
+            Err(FenError::InvalidSquare {
+                text: "33".to_string()
+            })
+        );
+        assert_eq!(
+            parse_fen("W:W21,K21:B1"),
+            Err(FenError::DuplicateSquare { square: 21 })
+        );
+        assert_eq!(
+            parse_fen("B:W2:B14"),
+            Err(FenError::UncrownedMan { square: 2 })
+        );
+        assert_eq!(
+            parse_fen("B:W14:B30"),
+            Err(FenError::UncrownedMan { square: 30 })
+        );
+        assert_eq!(
+            parse_fen("W:W21:B1:50"),
+            Err(FenError::InvalidCounter {
+                text: "50".to_string()
+            })
+        );
+    }
+}
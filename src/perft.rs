@@ -0,0 +1,146 @@
+//! Move generation counts for verifying `get_possible_actions`
+//!
+//! `perft` walks the full game tree to a fixed depth and counts the
+//! positions at the end. The counts for the starting positions are
+//! published, so any difference points at a bug in move generation or in
+//! `apply_action`.
+
+use crate::minmax::GameState;
+
+/// Number of positions exactly `depth` plies after `state`. Finished games
+/// end their branch early and are not counted.
+pub fn perft<T: GameState>(state: &T, depth: u8) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    if state.is_terminal() {
+        return 0;
+    }
+    let actions = state.get_possible_actions();
+    if depth == 1 {
+        return actions.len() as u64;
+    }
+    actions
+        .iter()
+        .map(|action| perft(&state.apply_action(action), depth - 1))
+        .sum()
+}
+
+/// `perft` split by the first move, for narrowing down a wrong count.
+pub fn divide<T: GameState>(state: &T, depth: u8) -> Vec<(T::Action, u64)> {
+    if depth == 0 || state.is_terminal() {
+        return Vec::new();
+    }
+    state
+        .get_possible_actions()
+        .into_iter()
+        .map(|action| {
+            let count = perft(&state.apply_action(&action), depth - 1);
+            (action, count)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::{CheckersBoard, RulesConfig};
+    use crate::four::FourRow;
+
+    fn counts<T: GameState>(state: &T, max_depth: u8) -> Vec<u64> {
+        (1..=max_depth).map(|depth| perft(state, depth)).collect()
+    }
+
+    #[test]
+    fn test_checkers_start_position() {
+        assert_eq!(
+            counts(&CheckersBoard::new(), 7),
+            [7, 49, 302, 1469, 7361, 36768, 179740]
+        );
+    }
+
+    #[test]
+    #[ignore = "slow in debug builds, run with --release --ignored"]
+    fn test_checkers_start_position_deep() {
+        // Depth 9 is the first where a checker can be crowned mid-capture
+        let state = CheckersBoard::new();
+        assert_eq!(perft(&state, 8), 845931);
+        assert_eq!(perft(&state, 9), 3963680);
+        assert_eq!(perft(&state, 10), 18391564);
+    }
+
+    fn variant(rules: RulesConfig) -> CheckersBoard {
+        CheckersBoard::new().with_rules(rules)
+    }
+
+    #[test]
+    fn test_checkers_variants() {
+        // Checkers capturing backwards make the difference from ply 5 on,
+        // the majority rule from ply 5 in Brazilian and ply 6 in Italian
+        assert_eq!(
+            counts(&variant(RulesConfig::RUSSIAN), 6),
+            [7, 49, 302, 1469, 7482, 37986]
+        );
+        assert_eq!(
+            counts(&variant(RulesConfig::BRAZILIAN), 6),
+            [7, 49, 302, 1469, 7473, 37628]
+        );
+        assert_eq!(
+            counts(&variant(RulesConfig::POOL), 6),
+            [7, 49, 302, 1469, 7482, 37986]
+        );
+        assert_eq!(
+            counts(&variant(RulesConfig::ITALIAN), 6),
+            [7, 49, 302, 1469, 7361, 36473]
+        );
+    }
+
+    #[test]
+    #[ignore = "slow in debug builds, run with --release --ignored"]
+    fn test_checkers_variants_deep() {
+        // Pool checkers part from Russian on ply 8, where a checker crowned
+        // in the middle of a capture goes on as a king in Russian only
+        let expected = [
+            (RulesConfig::RUSSIAN, [190146, 929905, 4570667, 22450647]),
+            (RulesConfig::BRAZILIAN, [187302, 907836, 4431847, 21566622]),
+            (RulesConfig::POOL, [190146, 929902, 4570615, 22442567]),
+            (RulesConfig::ITALIAN, [177532, 828783, 3860875, 17761384]),
+        ];
+        for (rules, counts) in expected {
+            let state = variant(rules);
+            assert_eq!([7, 8, 9, 10].map(|depth| perft(&state, depth)), counts);
+        }
+    }
+
+    #[test]
+    #[ignore = "slow in debug builds, run with --release --ignored"]
+    fn test_connect_four_empty_board_deep() {
+        // Ply 7 is the first that can end the game
+        assert_eq!(perft(&FourRow::new(), 8), 5673234);
+    }
+
+    #[test]
+    fn test_connect_four_empty_board() {
+        // 7^n until a column fills up on ply 6
+        assert_eq!(
+            counts(&FourRow::new(), 7),
+            [7, 49, 343, 2401, 16807, 117649, 823536]
+        );
+    }
+
+    #[test]
+    fn test_divide_sums_to_perft() {
+        let state = CheckersBoard::new();
+        let split = divide(&state, 4);
+        assert_eq!(split.len(), 7);
+        assert_eq!(split.iter().map(|(_, count)| count).sum::<u64>(), 1469);
+        assert!(divide(&state, 0).is_empty());
+    }
+
+    #[test]
+    fn test_finished_game_has_no_children() {
+        let state = FourRow::from_moves("1212121").unwrap();
+        assert_eq!(perft(&state, 0), 1);
+        assert_eq!(perft(&state, 1), 0);
+    }
+}
@@ -0,0 +1,378 @@
+//! Terminal game loop for the CLI binary
+//!
+//! Each side is either a human typing moves in the game's notation or the
+//! engine searching to a fixed depth or for a fixed time. Boards are printed
+//! with their `Debug` impls, optionally with the pieces coloured.
+
+use std::{
+    fmt::Debug,
+    hash::Hash,
+    io::{self, BufRead, Write},
+    time::Duration,
+};
+
+use fourrow::checkers::CheckersBoard;
+use fourrow::four::{FourRow, GameAction};
+use fourrow::minmax::{self, GameState, MakeMove, Player, SearchResult};
+use fourrow::pdn;
+use fourrow::tablebase::TablebaseBoard;
+
+/// How long the engine may think about a move.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchLimit {
+    Depth(u8),
+    Time(Duration),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Controller {
+    Human,
+    Ai(SearchLimit),
+}
+
+/// A game that can be played from the terminal.
+pub trait Playable: MakeMove + Clone + Debug + Hash + Eq + Send + Sync
+where
+    Self::Action: Send + Sync,
+{
+    fn side_to_move(&self) -> Player;
+
+    /// Parses one move typed by the user, `ply` counting from zero.
+    fn parse_move(&self, text: &str, ply: usize) -> Result<Self::Action, String>;
+
+    fn format_move(&self, action: &Self::Action) -> String;
+
+    /// Lines printed beside the rows of the board, e.g. the names of its
+    /// squares. The default has none.
+    fn legend(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+impl Playable for FourRow {
+    fn side_to_move(&self) -> Player {
+        self.current_player
+    }
+
+    fn parse_move(&self, text: &str, _ply: usize) -> Result<GameAction, String> {
+        let width = self.config.width;
+        let column = text
+            .parse::<usize>()
+            .ok()
+            .filter(|column| (1..=width).contains(column))
+            .ok_or_else(|| format!("'{}' is not a column from 1 to {}", text, width))?;
+        let action = GameAction {
+            column: (column - 1) as u8,
+        };
+        if !self.get_possible_actions().contains(&action) {
+            return Err(format!("column {} is full", column));
+        }
+        Ok(action)
+    }
+
+    fn format_move(&self, action: &GameAction) -> String {
+        (action.column + 1).to_string()
+    }
+}
+
+impl Playable for CheckersBoard {
+    fn side_to_move(&self) -> Player {
+        self.current_player()
+    }
+
+    fn parse_move(&self, text: &str, ply: usize) -> Result<Self::Action, String> {
+        pdn::parse_move(self, text, ply + 1).map_err(|err| err.to_string())
+    }
+
+    fn format_move(&self, action: &Self::Action) -> String {
+        pdn::format_move(self, action)
+    }
+
+    /// The PDN numbers of the dark squares, laid out like the board.
+    fn legend(&self) -> Vec<String> {
+        (0..8)
+            .rev()
+            .map(|row| {
+                let names: Vec<_> = (0..8)
+                    .map(|col| match pdn::position_to_square(row, col) {
+                        Some(square) => format!("{:>2}", square),
+                        None => "  ".to_string(),
+                    })
+                    .collect();
+                names.join(" ").trim_end().to_string()
+            })
+            .collect()
+    }
+}
+
+impl Playable for TablebaseBoard {
+    fn side_to_move(&self) -> Player {
+        self.board.side_to_move()
+    }
+
+    fn parse_move(&self, text: &str, ply: usize) -> Result<Self::Action, String> {
+        self.board.parse_move(text, ply)
+    }
+
+    fn format_move(&self, action: &Self::Action) -> String {
+        self.board.format_move(action)
+    }
+
+    fn legend(&self) -> Vec<String> {
+        self.board.legend()
+    }
+}
+
+/// Runs the engine for the side to move.
+pub fn search<T: Playable>(state: &T, limit: SearchLimit) -> SearchResult<T::Action>
+where
+    T::Action: Send + Sync,
+{
+    let maximizing_player = state.side_to_move() == Player::Player1;
+    match limit {
+        SearchLimit::Depth(depth) => search_depth(state, depth, maximizing_player),
+        SearchLimit::Time(budget) => {
+            minmax::iterative_deepening(state, maximizing_player, budget, u8::MAX)
+        }
+    }
+}
+
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+fn search_depth<T: Playable>(
+    state: &T,
+    depth: u8,
+    maximizing_player: bool,
+) -> SearchResult<T::Action>
+where
+    T::Action: Send + Sync,
+{
+    use fourrow::parallel::{ParallelConfig, parallel_minmax};
+
+    parallel_minmax(state, depth, maximizing_player, &ParallelConfig::default())
+}
+
+#[cfg(not(all(feature = "parallel", not(target_arch = "wasm32"))))]
+fn search_depth<T: Playable>(
+    state: &T,
+    depth: u8,
+    maximizing_player: bool,
+) -> SearchResult<T::Action>
+where
+    T::Action: Send + Sync,
+{
+    let mut table = minmax::TranspositionTable::new();
+    minmax::minmax_in_place(
+        state,
+        depth,
+        maximizing_player,
+        &mut table,
+        f32::NEG_INFINITY,
+        f32::INFINITY,
+    )
+}
+
+/// Plays a game from `state` until it ends or a human quits.
+pub fn play<T: Playable>(mut state: T, players: [Controller; 2], colour: bool) -> io::Result<()>
+where
+    T::Action: Send + Sync,
+{
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let mut ply = 0;
+
+    while !state.is_terminal() {
+        println!("{}", render_with_legend(&state, colour));
+        let player = state.side_to_move();
+        let controller = match player {
+            Player::Player1 => players[0],
+            Player::Player2 => players[1],
+        };
+
+        let action = match controller {
+            Controller::Human => {
+                let Some(action) = read_move(&state, ply, player, &mut input)? else {
+                    println!("Game abandoned.");
+                    return Ok(());
+                };
+                action
+            }
+            Controller::Ai(limit) => {
+                println!("{:?} is thinking...", player);
+                let result = search(&state, limit);
+                let action = result.best_action.expect("non-terminal position has moves");
+                println!(
+                    "{:?} plays {} (evaluation {}, depth {})",
+                    player,
+                    state.format_move(&action),
+                    result.evaluation,
+                    result.depth
+                );
+                action
+            }
+        };
+
+        state = state.apply_action(&action);
+        ply += 1;
+    }
+
+    println!("{}", render_with_legend(&state, colour));
+    match state.evaluate() {
+        Some(evaluation) if evaluation > 0.0 => println!("Player1 wins."),
+        Some(evaluation) if evaluation < 0.0 => println!("Player2 wins."),
+        _ => println!("Draw."),
+    }
+    Ok(())
+}
+
+/// Prompts until the user enters a legal move. Returns `None` on `quit` or
+/// the end of input.
+fn read_move<T: Playable>(
+    state: &T,
+    ply: usize,
+    player: Player,
+    input: &mut impl BufRead,
+) -> io::Result<Option<T::Action>>
+where
+    T::Action: Send + Sync,
+{
+    let legal: Vec<_> = state
+        .get_possible_actions()
+        .iter()
+        .map(|action| state.format_move(action))
+        .collect();
+    println!("Legal moves: {}", legal.join(" "));
+
+    loop {
+        print!("{:?} to move> ", player);
+        io::stdout().flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        match line.trim() {
+            "" => continue,
+            "quit" | "exit" => return Ok(None),
+            text => match state.parse_move(text, ply) {
+                Ok(action) => return Ok(Some(action)),
+                Err(err) => println!("Error: {}", err),
+            },
+        }
+    }
+}
+
+/// The `Debug` board, with Player1's pieces in red and Player2's in yellow
+/// if `colour` is set.
+fn render<T: Debug>(state: &T, colour: bool) -> String {
+    let board = format!("{:?}", state);
+    if !colour {
+        return board;
+    }
+    board
+        .lines()
+        .map(|line| {
+            line.split(' ')
+                .map(|token| match token {
+                    "X" | "Q" => format!("\x1b[31m{}\x1b[0m", token),
+                    "O" | "q" => format!("\x1b[33m{}\x1b[0m", token),
+                    _ => token.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// `render` with `Playable::legend` beside the board.
+fn render_with_legend<T: Playable>(state: &T, colour: bool) -> String
+where
+    T::Action: Send + Sync,
+{
+    let legend = state.legend();
+    render(state, colour)
+        .lines()
+        .enumerate()
+        .map(|(row, line)| match legend.get(row) {
+            Some(names) => format!("{}  {}", line, names),
+            None => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connect_four_moves() {
+        let state = FourRow::from_moves("111111").unwrap();
+        assert_eq!(state.parse_move("4", 6), Ok(GameAction { column: 3 }));
+        assert_eq!(state.format_move(&GameAction { column: 3 }), "4");
+        assert_eq!(
+            state.parse_move("1", 6),
+            Err("column 1 is full".to_string())
+        );
+        assert_eq!(
+            state.parse_move("8", 6),
+            Err("'8' is not a column from 1 to 7".to_string())
+        );
+    }
+
+    #[test]
+    fn test_checkers_moves() {
+        let state = CheckersBoard::new();
+        let action = state.parse_move("11-15", 0).unwrap();
+        assert_eq!(state.format_move(&action), "11-15");
+        assert_eq!(
+            state.parse_move("11-18", 0),
+            Err("illegal move '11-18' at ply 1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_read_move_retries_until_legal() {
+        let state = FourRow::new();
+        let mut input = "9\n\n3\n".as_bytes();
+        let action = read_move(&state, 0, Player::Player1, &mut input).unwrap();
+        assert_eq!(action, Some(GameAction { column: 2 }));
+
+        let mut input = "quit\n".as_bytes();
+        assert_eq!(
+            read_move(&state, 0, Player::Player1, &mut input).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_render_colours_pieces() {
+        let state = FourRow::from_moves("12").unwrap();
+        assert_eq!(render(&state, false), format!("{:?}", state));
+        let coloured = render(&state, true);
+        assert!(coloured.contains("\x1b[31mX\x1b[0m"));
+        assert!(coloured.contains("\x1b[33mO\x1b[0m"));
+    }
+
+    #[test]
+    fn test_board_labels_match_notation() {
+        // Connect Four columns are labelled as they are typed
+        let state = FourRow::new();
+        assert!(format!("{:?}", state).ends_with("1 2 3 4 5 6 7 "));
+
+        let rendered = render_with_legend(&CheckersBoard::new(), false);
+        let lines: Vec<_> = rendered.lines().collect();
+        assert_eq!(lines[0], ". O . O . O . O      32    31    30    29");
+        assert_eq!(lines[7], "X . X . X . X .    4     3     2     1");
+        assert_eq!(lines[8], "Current Player: Player1");
+    }
+
+    #[test]
+    fn test_ai_search_limits() {
+        let state = FourRow::from_moves("4455").unwrap();
+        let result = search(&state, SearchLimit::Depth(3));
+        assert_eq!(result.depth, 3);
+        let result = search(&state, SearchLimit::Time(Duration::from_millis(20)));
+        assert!(result.best_action.is_some());
+    }
+}
@@ -0,0 +1,534 @@
+//! Perfect-play Connect Four solver
+//!
+//! Computes the exact game-theoretic value of a position instead of a
+//! heuristic estimate. The search is a negamax with alpha-beta pruning
+//! driven by null-window probes, and relies on:
+//! - pruning moves that hand the opponent an immediate win
+//! - ordering moves by the number of winning threats they create, center
+//!   columns first on ties
+//! - a large transposition table storing lower and upper bounds
+//!
+//! ## Scores
+//!
+//! Scores are from the point of view of the side to move. Winning with the
+//! `n + 1`-th disc of the game scores `(43 - n) / 2`, so earlier wins score
+//! higher; losses score the negated value for the opponent and a draw
+//! scores `0`. This lets the solver prefer the fastest win and the slowest
+//! loss.
+
+use serde::Serialize;
+
+use crate::four::{BOARD_HEIGHT, BOARD_WIDTH, GameAction};
+use crate::four_bitboard::FourRowBitboard;
+use crate::minmax::{GameResult, GameState, Player};
+
+const BOARD_SIZE: i32 = (BOARD_WIDTH * BOARD_HEIGHT) as i32;
+const MIN_SCORE: i32 = -BOARD_SIZE / 2 + 3;
+const MAX_SCORE: i32 = (BOARD_SIZE + 1) / 2 - 3;
+
+/// Columns in the order they are tried, center first.
+const COLUMN_ORDER: [usize; BOARD_WIDTH] = [3, 2, 4, 1, 5, 0, 6];
+
+/// Number of transposition table entries. Prime, so that the 32 bits of the
+/// key stored in an entry together with its index identify a position.
+pub const DEFAULT_TABLE_SIZE: usize = 8_388_617;
+
+const fn bottom_mask() -> u64 {
+    let mut mask = 0;
+    let mut col = 0;
+    while col < BOARD_WIDTH {
+        mask |= 1 << (col * (BOARD_HEIGHT + 1));
+        col += 1;
+    }
+    mask
+}
+
+const BOTTOM_MASK: u64 = bottom_mask();
+const BOARD_MASK: u64 = BOTTOM_MASK * ((1 << BOARD_HEIGHT) - 1);
+
+const fn top_mask(col: usize) -> u64 {
+    1 << (BOARD_HEIGHT - 1 + col * (BOARD_HEIGHT + 1))
+}
+
+const fn column_mask(col: usize) -> u64 {
+    ((1 << BOARD_HEIGHT) - 1) << (col * (BOARD_HEIGHT + 1))
+}
+
+/// Outcome of a position for the side to move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Outcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Solution {
+    pub outcome: Outcome,
+    /// Score for the side to move, see the module documentation.
+    pub score: i32,
+    /// Plies until the winning disc is dropped with perfect play from both
+    /// sides, `None` for a draw.
+    pub moves_to_end: Option<u32>,
+}
+
+impl Solution {
+    fn from_score(score: i32, moves_played: i32) -> Self {
+        // The winner drops its final disc when `moves` discs are on the
+        // board, with `moves` of the winner's parity.
+        let moves_to_end = |winner_parity: i32| {
+            let moves = BOARD_SIZE - 2 * score.abs() + winner_parity;
+            (moves + 1 - moves_played) as u32
+        };
+        match score {
+            0 => Solution {
+                outcome: Outcome::Draw,
+                score,
+                moves_to_end: None,
+            },
+            s if s > 0 => Solution {
+                outcome: Outcome::Win,
+                score,
+                moves_to_end: Some(moves_to_end(moves_played % 2)),
+            },
+            _ => Solution {
+                outcome: Outcome::Loss,
+                score,
+                moves_to_end: Some(moves_to_end((moves_played + 1) % 2)),
+            },
+        }
+    }
+}
+
+/// Position seen from the side to move, as used by the search.
+#[derive(Clone, Copy)]
+struct Position {
+    /// Discs of the side to move.
+    current: u64,
+    /// Discs of both players.
+    mask: u64,
+    moves: i32,
+}
+
+impl Position {
+    fn from_bitboard(state: &FourRowBitboard) -> Self {
+        let current = match state.current_player {
+            Player::Player1 => state.player1,
+            Player::Player2 => state.player2,
+        };
+        let mask = state.player1 | state.player2;
+        Position {
+            current,
+            mask,
+            moves: mask.count_ones() as i32,
+        }
+    }
+
+    /// Unique key of the position, thanks to the empty sentinel row.
+    fn key(&self) -> u64 {
+        self.current + self.mask
+    }
+
+    fn play(&mut self, move_bit: u64) {
+        self.current ^= self.mask;
+        self.mask |= move_bit;
+        self.moves += 1;
+    }
+
+    fn can_play(&self, col: usize) -> bool {
+        self.mask & top_mask(col) == 0
+    }
+
+    /// Lowest free cell of every column that is not full.
+    fn possible(&self) -> u64 {
+        (self.mask + BOTTOM_MASK) & BOARD_MASK
+    }
+
+    fn can_win_next(&self) -> bool {
+        self.winning_cells() & self.possible() != 0
+    }
+
+    fn winning_cells(&self) -> u64 {
+        winning_cells(self.current, self.mask)
+    }
+
+    fn opponent_winning_cells(&self) -> u64 {
+        winning_cells(self.current ^ self.mask, self.mask)
+    }
+
+    /// Moves that do not let the opponent win on the next ply. Assumes the
+    /// side to move cannot win immediately.
+    fn possible_non_losing_moves(&self) -> u64 {
+        let mut possible = self.possible();
+        let opponent_wins = self.opponent_winning_cells();
+        let forced = possible & opponent_wins;
+        if forced != 0 {
+            if forced & (forced - 1) != 0 {
+                // The opponent has two immediate threats, we cannot block both.
+                return 0;
+            }
+            possible = forced;
+        }
+        // Never play directly below a cell that wins for the opponent.
+        possible & !(opponent_wins >> 1)
+    }
+
+    /// Number of winning cells the side to move has after playing `move_bit`.
+    fn move_score(&self, move_bit: u64) -> u32 {
+        winning_cells(self.current | move_bit, self.mask).count_ones()
+    }
+}
+
+/// Empty cells that would complete four in a row for `position`.
+fn winning_cells(position: u64, mask: u64) -> u64 {
+    let h = BOARD_HEIGHT as u32;
+
+    // Vertical
+    let mut cells = (position << 1) & (position << 2) & (position << 3);
+
+    // Horizontal and both diagonals
+    for shift in [h + 1, h, h + 2] {
+        let pair = (position << shift) & (position << (2 * shift));
+        cells |= pair & (position << (3 * shift));
+        cells |= pair & (position >> shift);
+        let pair = (position >> shift) & (position >> (2 * shift));
+        cells |= pair & (position << shift);
+        cells |= pair & (position >> (3 * shift));
+    }
+
+    cells & (BOARD_MASK ^ mask)
+}
+
+/// Fixed-size table storing one bound per position.
+///
+/// Values are encoded so that `0` means empty: upper bounds occupy
+/// `1..=MAX_SCORE - MIN_SCORE + 1` and lower bounds the range above.
+struct BoundTable {
+    keys: Vec<u32>,
+    values: Vec<u8>,
+}
+
+impl BoundTable {
+    fn new(size: usize) -> Self {
+        BoundTable {
+            keys: vec![0; size],
+            values: vec![0; size],
+        }
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (key % self.keys.len() as u64) as usize
+    }
+
+    fn put(&mut self, key: u64, value: u8) {
+        let index = self.index(key);
+        self.keys[index] = key as u32;
+        self.values[index] = value;
+    }
+
+    fn get(&self, key: u64) -> u8 {
+        let index = self.index(key);
+        if self.keys[index] == key as u32 {
+            self.values[index]
+        } else {
+            0
+        }
+    }
+}
+
+/// Connect Four solver for the classic 7x6 board.
+///
+/// The transposition table is kept between calls, so solving successive
+/// positions of one game gets faster as it fills up.
+pub struct Solver {
+    table: BoundTable,
+    node_count: u64,
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        Self::with_table_size(DEFAULT_TABLE_SIZE)
+    }
+
+    /// Creates a solver with `entries` transposition table slots. A prime
+    /// larger than 2^17 keeps stored keys unambiguous.
+    pub fn with_table_size(entries: usize) -> Self {
+        Solver {
+            table: BoundTable::new(entries),
+            node_count: 0,
+        }
+    }
+
+    /// Number of positions searched since the solver was created.
+    pub fn node_count(&self) -> u64 {
+        self.node_count
+    }
+
+    /// Proves the exact value of `state` for the side to move.
+    pub fn solve(&mut self, state: &FourRowBitboard) -> Solution {
+        let position = Position::from_bitboard(state);
+        let score = match state.get_terminal() {
+            // The previous player just won with its last disc.
+            GameResult::Win(_) => -(BOARD_SIZE + 1 - position.moves + 1) / 2,
+            GameResult::Draw => 0,
+            GameResult::Ongoing => self.score(&position),
+        };
+        Solution::from_score(score, position.moves)
+    }
+
+    /// A move that achieves the solved score, preferring center columns.
+    pub fn best_move(&mut self, state: &FourRowBitboard) -> Option<GameAction> {
+        if state.is_terminal() {
+            return None;
+        }
+        let position = Position::from_bitboard(state);
+        let playable = COLUMN_ORDER
+            .into_iter()
+            .filter(|&col| position.can_play(col));
+        let move_bit = |col| position.possible() & column_mask(col);
+
+        // Nothing beats winning right away.
+        if let Some(col) = playable
+            .clone()
+            .find(|&col| position.winning_cells() & move_bit(col) != 0)
+        {
+            return Some(GameAction { column: col as u8 });
+        }
+
+        let mut best: Option<(i32, usize)> = None;
+        for col in playable {
+            let mut next = position;
+            next.play(move_bit(col));
+            let score = -self.score(&next);
+            if best.is_none_or(|(best_score, _)| score > best_score) {
+                best = Some((score, col));
+            }
+        }
+        best.map(|(_, col)| GameAction { column: col as u8 })
+    }
+
+    fn score(&mut self, position: &Position) -> i32 {
+        if position.can_win_next() {
+            return (BOARD_SIZE + 1 - position.moves) / 2;
+        }
+        if position.moves == BOARD_SIZE {
+            return 0;
+        }
+
+        // Narrow the score down with null-window searches, probing near
+        // zero first because most positions are close to a draw.
+        let mut min = -(BOARD_SIZE - position.moves) / 2;
+        let mut max = (BOARD_SIZE + 1 - position.moves) / 2;
+        while min < max {
+            let mut med = min + (max - min) / 2;
+            if med <= 0 && min / 2 < med {
+                med = min / 2;
+            } else if med >= 0 && max / 2 > med {
+                med = max / 2;
+            }
+            let result = self.negamax(position, med, med + 1);
+            if result <= med {
+                max = result;
+            } else {
+                min = result;
+            }
+        }
+        min
+    }
+
+    /// Score of a position where the side to move cannot win immediately,
+    /// exact when it lies inside `(alpha, beta)`.
+    fn negamax(&mut self, position: &Position, mut alpha: i32, mut beta: i32) -> i32 {
+        self.node_count += 1;
+
+        let possible = position.possible_non_losing_moves();
+        if possible == 0 {
+            // Every move lets the opponent win next ply.
+            return -(BOARD_SIZE - position.moves) / 2;
+        }
+        if position.moves >= BOARD_SIZE - 2 {
+            // Neither player can win with the last two discs.
+            return 0;
+        }
+
+        // The opponent cannot win on its next ply, so the lower bound rises.
+        let min = -(BOARD_SIZE - 2 - position.moves) / 2;
+        if alpha < min {
+            alpha = min;
+            if alpha >= beta {
+                return alpha;
+            }
+        }
+        // We cannot win on this ply either.
+        let max = (BOARD_SIZE - 1 - position.moves) / 2;
+        if beta > max {
+            beta = max;
+            if alpha >= beta {
+                return beta;
+            }
+        }
+
+        let key = position.key();
+        let value = self.table.get(key) as i32;
+        if value > MAX_SCORE - MIN_SCORE + 1 {
+            let min = value + 2 * MIN_SCORE - MAX_SCORE - 2;
+            if alpha < min {
+                alpha = min;
+                if alpha >= beta {
+                    return alpha;
+                }
+            }
+        } else if value != 0 {
+            let max = value + MIN_SCORE - 1;
+            if beta > max {
+                beta = max;
+                if alpha >= beta {
+                    return beta;
+                }
+            }
+        }
+
+        let mut moves: Vec<(u64, u32)> = COLUMN_ORDER
+            .iter()
+            .map(|&col| possible & column_mask(col))
+            .filter(|&move_bit| move_bit != 0)
+            .map(|move_bit| (move_bit, position.move_score(move_bit)))
+            .collect();
+        // Stable, so ties keep the center-first column order.
+        moves.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+
+        for (move_bit, _) in moves {
+            let mut next = *position;
+            next.play(move_bit);
+            let score = -self.negamax(&next, -beta, -alpha);
+            if score >= beta {
+                self.table
+                    .put(key, (score + MAX_SCORE - 2 * MIN_SCORE + 2) as u8);
+                return score;
+            }
+            alpha = alpha.max(score);
+        }
+
+        self.table.put(key, (alpha - MIN_SCORE + 1) as u8);
+        alpha
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(columns: &[u8]) -> FourRowBitboard {
+        columns
+            .iter()
+            .fold(FourRowBitboard::new(), |state, &column| {
+                state.apply_action(&GameAction { column })
+            })
+    }
+
+    /// Exhaustive negamax returning the score and the plies until the end.
+    fn brute_force(state: &FourRowBitboard) -> (i32, u32) {
+        let moves = (state.player1 | state.player2).count_ones() as i32;
+        if state.get_terminal() == GameResult::Draw {
+            return (0, 0);
+        }
+        state
+            .get_possible_actions()
+            .iter()
+            .map(|action| {
+                let next = state.apply_action(action);
+                if next.get_terminal() == GameResult::Win(state.current_player) {
+                    ((BOARD_SIZE + 1 - moves) / 2, 1)
+                } else {
+                    let (score, plies) = brute_force(&next);
+                    (-score, plies + 1)
+                }
+            })
+            // Highest score, and among those the fastest win or slowest loss.
+            .max_by_key(|&(score, plies)| {
+                (
+                    score,
+                    if score > 0 {
+                        -(plies as i32)
+                    } else {
+                        plies as i32
+                    },
+                )
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_immediate_win() {
+        let state = play(&[0, 0, 1, 1, 2, 2]);
+        let mut solver = Solver::with_table_size(1_048_583);
+        let solution = solver.solve(&state);
+        assert_eq!(solution.outcome, Outcome::Win);
+        assert_eq!(solution.moves_to_end, Some(1));
+        assert_eq!(solver.best_move(&state), Some(GameAction { column: 3 }));
+    }
+
+    #[test]
+    fn test_finished_game() {
+        let state = play(&[0, 0, 1, 1, 2, 2, 3]);
+        let solution = Solver::with_table_size(1_048_583).solve(&state);
+        assert_eq!(solution.outcome, Outcome::Loss);
+        assert_eq!(solution.moves_to_end, Some(0));
+    }
+
+    #[test]
+    fn test_matches_brute_force_on_late_positions() {
+        let mut solver = Solver::with_table_size(1_048_583);
+        let mut rng: u64 = 0x9e37_79b9_7f4a_7c15;
+        let mut checked = 0;
+        while checked < 20 {
+            // Random game with 7 empty cells left
+            let mut state = FourRowBitboard::new();
+            for _ in 0..35 {
+                if state.is_terminal() {
+                    break;
+                }
+                let actions = state.get_possible_actions();
+                rng ^= rng << 13;
+                rng ^= rng >> 7;
+                rng ^= rng << 17;
+                state = state.apply_action(&actions[rng as usize % actions.len()]);
+            }
+            if state.is_terminal() {
+                continue;
+            }
+
+            let (score, plies) = brute_force(&state);
+            let solution = solver.solve(&state);
+            assert_eq!(solution.score, score, "{:?}", state);
+            if score != 0 {
+                assert_eq!(solution.moves_to_end, Some(plies), "{:?}", state);
+            }
+
+            let best = solver.best_move(&state).unwrap();
+            let after = state.apply_action(&best);
+            let best_score = if after.is_terminal() {
+                solution.score
+            } else {
+                -solver.solve(&after).score
+            };
+            assert_eq!(best_score, score, "{:?}", state);
+            checked += 1;
+        }
+    }
+
+    #[test]
+    #[ignore = "takes about 15 minutes even in release builds"]
+    fn test_empty_board_is_first_player_win() {
+        let solution = Solver::new().solve(&FourRowBitboard::new());
+        assert_eq!(solution.outcome, Outcome::Win);
+        assert_eq!(solution.score, 1);
+        assert_eq!(solution.moves_to_end, Some(41));
+    }
+}
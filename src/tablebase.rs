@@ -0,0 +1,781 @@
+//! Checkers endgame tablebases
+//!
+//! A tablebase holds the exact value of every position with up to
+//! `max_pieces` pieces: whether the side to move wins, loses or draws with
+//! perfect play, and how many plies the game lasts until the last piece of
+//! the loser is taken. It is generated by retrograde analysis. Positions
+//! are sorted into tables by their material, and each table is solved
+//! backwards from the positions whose value is known after one move, in
+//! order of distance, so every win is as short and every loss as long as
+//! possible. Captures and promotions lead to tables that are solved
+//! earlier, since they have fewer pieces or fewer men.
+//!
+//! The rules are the English ones of `CheckersBoard`, where a side without
+//! moves draws. Boards played by other rules are not in the tablebase. The
+//! 50-move rule is ignored; a won endgame is assumed to be won in time.
+//!
+//! ## Indexing
+//!
+//! Pieces are numbered by the 32 dark squares, four per row from the
+//! bottom. Within a table a position is indexed by the side to move and by
+//! the squares of its four groups of pieces, Player1's men and kings and
+//! then Player2's, each group ranked as a combination. Men are never on
+//! their crowning row, so their groups only range over 28 squares. Indices
+//! where two groups share a square do not belong to a position.
+//!
+//! ## File Format
+//!
+//! The magic bytes `FRTB`, a version byte and `max_pieces`, then every table
+//! in the order of `materials`: one byte per index, 0 for a draw and
+//! otherwise the number of plies until the end, odd if the side to move
+//! wins and even if it loses.
+
+use std::{collections::HashMap, fmt::Display, hash::Hash, sync::Arc};
+
+use crate::checkers::{CheckersBoard, CheckersTile, RulesConfig};
+use crate::minmax::{GameState, MakeMove, Player};
+
+const MAGIC: &[u8; 4] = b"FRTB";
+const VERSION: u8 = 1;
+
+/// Largest number of pieces a tablebase may cover, which keeps the tables
+/// of a file header within memory.
+pub const MAX_PIECES: u8 = 5;
+
+/// `CheckersBoard::evaluate` for a won game. Tablebase wins score less the
+/// longer they take, so the search heads for the fastest one.
+const WIN_SCORE: f32 = 1000.0;
+
+/// Error when generating or reading a tablebase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TablebaseError {
+    /// The data does not start with the tablebase magic bytes.
+    NotATablebase,
+    /// The file was written by a newer version of the format.
+    UnsupportedVersion(u8),
+    /// The header claims a number of pieces outside `2..=MAX_PIECES`.
+    UnsupportedPieces(u8),
+    /// The data is not as long as the tables for its number of pieces.
+    WrongLength { expected: usize, found: usize },
+    /// An endgame lasts longer than the 255 plies a table entry can hold.
+    EndgameTooLong,
+}
+
+impl Display for TablebaseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TablebaseError::NotATablebase => write!(f, "not an endgame tablebase"),
+            TablebaseError::UnsupportedVersion(version) => {
+                write!(f, "unsupported tablebase version {}", version)
+            }
+            TablebaseError::UnsupportedPieces(pieces) => write!(
+                f,
+                "tablebases cover 2 to {} pieces, not {}",
+                MAX_PIECES, pieces
+            ),
+            TablebaseError::WrongLength { expected, found } => write!(
+                f,
+                "tablebase should be {} bytes long but is {}",
+                expected, found
+            ),
+            TablebaseError::EndgameTooLong => write!(
+                f,
+                "an endgame lasts longer than {} plies, the most a tablebase stores",
+                u8::MAX
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TablebaseError {}
+
+/// Value of a position for the side to move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EndgameValue {
+    /// The side to move takes the last opposing piece in this many plies.
+    Win(u8),
+    /// The side to move loses its last piece in this many plies.
+    Loss(u8),
+    Draw,
+}
+
+impl EndgameValue {
+    fn from_byte(byte: u8) -> Self {
+        match byte {
+            0 => EndgameValue::Draw,
+            plies if plies % 2 == 1 => EndgameValue::Win(plies),
+            plies => EndgameValue::Loss(plies),
+        }
+    }
+}
+
+/// Number of men and kings of each player, Player1's first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Material {
+    pub men: [u8; 2],
+    pub kings: [u8; 2],
+}
+
+impl Material {
+    fn of(board: &CheckersBoard) -> Self {
+        let mut material = Material {
+            men: [0; 2],
+            kings: [0; 2],
+        };
+        for tile in board.board().iter().flatten() {
+            match *tile {
+                CheckersTile::Checker(player) => material.men[side(player)] += 1,
+                CheckersTile::Queen(player) => material.kings[side(player)] += 1,
+                CheckersTile::Empty => {}
+            }
+        }
+        material
+    }
+
+    fn pieces(&self, side: usize) -> u8 {
+        self.men[side] + self.kings[side]
+    }
+
+    /// Groups in index order: the number of pieces and of squares they can
+    /// stand on.
+    fn groups(&self) -> [(u8, u8); 4] {
+        [
+            (self.men[0], 28),
+            (self.kings[0], 32),
+            (self.men[1], 28),
+            (self.kings[1], 32),
+        ]
+    }
+
+    /// Number of indices for one side to move.
+    fn size(&self) -> usize {
+        self.groups()
+            .iter()
+            .map(|&(pieces, squares)| binomial(squares, pieces))
+            .product()
+    }
+}
+
+/// Every material with at least one piece per side and at most `max_pieces`
+/// in total, in the order tables are solved and stored.
+pub fn materials(max_pieces: u8) -> Vec<Material> {
+    let mut materials = Vec::new();
+    for men1 in 0..=max_pieces {
+        for kings1 in 0..=max_pieces - men1 {
+            for men2 in 0..=max_pieces - men1 - kings1 {
+                for kings2 in 0..=max_pieces - men1 - kings1 - men2 {
+                    let material = Material {
+                        men: [men1, men2],
+                        kings: [kings1, kings2],
+                    };
+                    if material.pieces(0) > 0 && material.pieces(1) > 0 {
+                        materials.push(material);
+                    }
+                }
+            }
+        }
+    }
+    // Captures lose a piece and promotions a man, so both lead to tables
+    // sorted before the one they start from
+    materials.sort_by_key(|material| {
+        (
+            material.pieces(0) + material.pieces(1),
+            material.men[0] + material.men[1],
+        )
+    });
+    materials
+}
+
+fn side(player: Player) -> usize {
+    match player {
+        Player::Player1 => 0,
+        Player::Player2 => 1,
+    }
+}
+
+fn binomial(n: u8, k: u8) -> usize {
+    if k > n {
+        return 0;
+    }
+    (0..k as usize).fold(1, |result, i| result * (n as usize - i) / (i + 1))
+}
+
+/// Number of a dark square, `None` for a light one.
+fn square(row: usize, col: usize) -> Option<u8> {
+    (row + col)
+        .is_multiple_of(2)
+        .then_some((row * 4 + col / 2) as u8)
+}
+
+fn row_col(square: u8) -> (usize, usize) {
+    let row = square as usize / 4;
+    (row, 2 * (square as usize % 4) + row % 2)
+}
+
+/// Squares of the four groups, Player2's men counted from the second row.
+/// `None` if a man stands on its crowning row.
+fn group_squares(board: &CheckersBoard) -> Option<[Vec<u8>; 4]> {
+    let mut groups: [Vec<u8>; 4] = Default::default();
+    for (row, tiles) in board.board().iter().enumerate() {
+        for (col, &tile) in tiles.iter().enumerate() {
+            let Some(square) = square(row, col) else {
+                continue;
+            };
+            match tile {
+                CheckersTile::Checker(Player::Player1) if square < 28 => groups[0].push(square),
+                CheckersTile::Checker(Player::Player2) if square >= 4 => groups[2].push(square - 4),
+                CheckersTile::Checker(_) => return None,
+                CheckersTile::Queen(Player::Player1) => groups[1].push(square),
+                CheckersTile::Queen(Player::Player2) => groups[3].push(square),
+                CheckersTile::Empty => {}
+            }
+        }
+    }
+    Some(groups)
+}
+
+/// Rank of a set of squares in ascending order among all sets of its size.
+fn rank(squares: &[u8]) -> usize {
+    squares
+        .iter()
+        .enumerate()
+        .map(|(i, &square)| binomial(square, i as u8 + 1))
+        .sum()
+}
+
+/// The set of `pieces` squares below `squares` with the given `rank`.
+fn unrank(mut rank: usize, pieces: u8, squares: u8) -> Vec<u8> {
+    let mut set = vec![0; pieces as usize];
+    let mut square = squares;
+    for i in (1..=pieces).rev() {
+        square -= 1;
+        while binomial(square, i) > rank {
+            square -= 1;
+        }
+        rank -= binomial(square, i);
+        set[i as usize - 1] = square;
+    }
+    set
+}
+
+/// Index of `board` in the table of its material, `None` if it has a man
+/// on its crowning row.
+fn index(board: &CheckersBoard, material: &Material) -> Option<usize> {
+    let placement = group_squares(board)?
+        .iter()
+        .zip(material.groups())
+        .fold(0, |index, (squares, (pieces, range))| {
+            index * binomial(range, pieces) + rank(squares)
+        });
+    Some(side(board.current_player()) * material.size() + placement)
+}
+
+/// The position at `index` in the table of `material`, `None` if two
+/// pieces would share a square.
+fn position(material: &Material, index: usize) -> Option<CheckersBoard> {
+    let size = material.size();
+    let current_player = if index < size {
+        Player::Player1
+    } else {
+        Player::Player2
+    };
+    let mut placement = index % size;
+    let mut board = [[CheckersTile::Empty; 8]; 8];
+    let tiles = [
+        CheckersTile::Checker(Player::Player1),
+        CheckersTile::Queen(Player::Player1),
+        CheckersTile::Checker(Player::Player2),
+        CheckersTile::Queen(Player::Player2),
+    ];
+    for (group, &(pieces, range)) in material.groups().iter().enumerate().rev() {
+        let count = binomial(range, pieces);
+        let squares = unrank(placement % count, pieces, range);
+        placement /= count;
+        for square in squares {
+            let square = if group == 2 { square + 4 } else { square };
+            let (row, col) = row_col(square);
+            if board[row][col] != CheckersTile::Empty {
+                return None;
+            }
+            board[row][col] = tiles[group];
+        }
+    }
+    Some(CheckersBoard::from_board(board, current_player, 0))
+}
+
+/// Exact values of all checkers positions with few pieces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tablebase {
+    max_pieces: u8,
+    tables: HashMap<Material, Vec<u8>>,
+}
+
+impl Tablebase {
+    /// Solves every position with at most `max_pieces` pieces. Four pieces
+    /// take a few minutes in a release build.
+    pub fn generate(max_pieces: u8) -> Result<Self, TablebaseError> {
+        if !(2..=MAX_PIECES).contains(&max_pieces) {
+            return Err(TablebaseError::UnsupportedPieces(max_pieces));
+        }
+        let mut tablebase = Tablebase {
+            max_pieces,
+            tables: HashMap::new(),
+        };
+        for material in materials(max_pieces) {
+            let table = tablebase.solve(&material)?;
+            tablebase.tables.insert(material, table);
+        }
+        Ok(tablebase)
+    }
+
+    pub fn max_pieces(&self) -> u8 {
+        self.max_pieces
+    }
+
+    /// Value of `board` for the side to move, `None` if it has too many
+    /// pieces, a side has none left, a man stands on its crowning row or it
+    /// is not played by English rules.
+    pub fn probe(&self, board: &CheckersBoard) -> Option<EndgameValue> {
+        if board.rules() != RulesConfig::ENGLISH {
+            return None;
+        }
+        let material = Material::of(board);
+        if material.pieces(0) == 0 || material.pieces(1) == 0 {
+            return None;
+        }
+        let table = self.tables.get(&material)?;
+        let byte = table.get(index(board, &material)?)?;
+        Some(EndgameValue::from_byte(*byte))
+    }
+
+    /// Value of `board` from Player1's point of view on the scale of
+    /// `CheckersBoard::evaluate`.
+    pub fn score(&self, board: &CheckersBoard) -> Option<f32> {
+        let score = match self.probe(board)? {
+            EndgameValue::Win(plies) => WIN_SCORE - plies as f32,
+            EndgameValue::Loss(plies) => plies as f32 - WIN_SCORE,
+            EndgameValue::Draw => 0.0,
+        };
+        Some(match board.current_player() {
+            Player::Player1 => score,
+            Player::Player2 => -score,
+        })
+    }
+
+    /// Retrograde analysis of one table, with every table its moves can
+    /// lead to already solved.
+    fn solve(&self, material: &Material) -> Result<Vec<u8>, TablebaseError> {
+        let len = 2 * material.size();
+        let mut values = vec![0u8; len];
+        let mut solved = vec![false; len];
+        // Moves not yet known to lose, and the longest of those that do
+        let mut open_moves = vec![0u32; len];
+        let mut longest_loss = vec![0u8; len];
+        // Moves within the table, as (child, parent)
+        let mut moves = Vec::new();
+        // Positions whose value is found at each distance
+        let mut found: Vec<Vec<u32>> = vec![Vec::new(); u8::MAX as usize + 2];
+
+        for index in 0..len {
+            let Some(mut board) = position(material, index) else {
+                solved[index] = true;
+                continue;
+            };
+            let actions = board.get_possible_actions();
+            open_moves[index] = actions.len() as u32;
+            for action in &actions {
+                let undo = board.make_move(action);
+                let child = Material::of(&board);
+                // Men reaching their last row are crowned, so every child has an index
+                let child_index = |material| self::index(&board, material).unwrap();
+                if child.pieces(side(board.current_player())) == 0 {
+                    // Taking the last piece wins at once
+                    found[1].push(index as u32);
+                } else if child == *material {
+                    moves.push((child_index(material) as u32, index as u32));
+                } else {
+                    let table = &self.tables[&child];
+                    match EndgameValue::from_byte(table[child_index(&child)]) {
+                        EndgameValue::Loss(plies) => found[plies as usize + 1].push(index as u32),
+                        EndgameValue::Win(plies) => {
+                            open_moves[index] -= 1;
+                            longest_loss[index] = longest_loss[index].max(plies);
+                        }
+                        EndgameValue::Draw => {}
+                    }
+                }
+                board.unmake_move(undo);
+            }
+            if actions.is_empty() {
+                solved[index] = true;
+            } else if open_moves[index] == 0 {
+                found[longest_loss[index] as usize + 1].push(index as u32);
+            }
+        }
+
+        // Parents of every position, grouped by child
+        moves.sort_unstable();
+        let mut parents_start = vec![0; len + 1];
+        for &(child, _) in &moves {
+            parents_start[child as usize + 1] += 1;
+        }
+        for index in 0..len {
+            parents_start[index + 1] += parents_start[index];
+        }
+
+        for plies in 1..found.len() {
+            while let Some(index) = found[plies].pop() {
+                let index = index as usize;
+                if solved[index] {
+                    continue;
+                }
+                solved[index] = true;
+                values[index] = u8::try_from(plies).map_err(|_| TablebaseError::EndgameTooLong)?;
+
+                for &(_, parent) in &moves[parents_start[index]..parents_start[index + 1]] {
+                    let parent = parent as usize;
+                    if solved[parent] {
+                        continue;
+                    }
+                    let parent_plies = if plies % 2 == 0 {
+                        // The parent can move into this loss
+                        plies + 1
+                    } else {
+                        open_moves[parent] -= 1;
+                        longest_loss[parent] = longest_loss[parent].max(plies as u8);
+                        if open_moves[parent] > 0 {
+                            continue;
+                        }
+                        longest_loss[parent] as usize + 1
+                    };
+                    found[parent_plies].push(parent as u32);
+                }
+            }
+        }
+        Ok(values)
+    }
+
+    /// Reads a tablebase written by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TablebaseError> {
+        if bytes.len() < 6 || &bytes[..4] != MAGIC {
+            return Err(TablebaseError::NotATablebase);
+        }
+        if bytes[4] != VERSION {
+            return Err(TablebaseError::UnsupportedVersion(bytes[4]));
+        }
+        let max_pieces = bytes[5];
+        if !(2..=MAX_PIECES).contains(&max_pieces) {
+            return Err(TablebaseError::UnsupportedPieces(max_pieces));
+        }
+        let materials = materials(max_pieces);
+        let expected = materials
+            .iter()
+            .try_fold(6usize, |length, material| {
+                material
+                    .size()
+                    .checked_mul(2)
+                    .and_then(|size| length.checked_add(size))
+            })
+            .ok_or(TablebaseError::UnsupportedPieces(max_pieces))?;
+        if bytes.len() != expected {
+            return Err(TablebaseError::WrongLength {
+                expected,
+                found: bytes.len(),
+            });
+        }
+
+        let mut tables = HashMap::new();
+        let mut rest = &bytes[6..];
+        for material in materials {
+            let (table, tail) = rest.split_at(2 * material.size());
+            tables.insert(material, table.to_vec());
+            rest = tail;
+        }
+        Ok(Tablebase { max_pieces, tables })
+    }
+
+    /// The tablebase in the file format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.push(self.max_pieces);
+        for material in materials(self.max_pieces) {
+            bytes.extend_from_slice(&self.tables[&material]);
+        }
+        bytes
+    }
+}
+
+/// A checkers position whose searches stop at positions in `tablebase`
+/// and score them exactly.
+///
+/// Equality and hashing only look at the position, the tablebase is the
+/// same for every position in one search.
+#[derive(Clone)]
+pub struct TablebaseBoard {
+    pub board: CheckersBoard,
+    pub tablebase: Arc<Tablebase>,
+}
+
+impl PartialEq for TablebaseBoard {
+    fn eq(&self, other: &Self) -> bool {
+        self.board == other.board
+    }
+}
+
+impl Eq for TablebaseBoard {}
+
+impl Hash for TablebaseBoard {
+    fn hash<H: std::hash::Hasher>(&self, hasher: &mut H) {
+        self.board.hash(hasher);
+    }
+}
+
+impl std::fmt::Debug for TablebaseBoard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.board.fmt(f)
+    }
+}
+
+impl GameState for TablebaseBoard {
+    type Action = <CheckersBoard as GameState>::Action;
+
+    fn get_possible_actions(&self) -> Vec<Self::Action> {
+        self.board.get_possible_actions()
+    }
+
+    fn apply_action(&self, action: &Self::Action) -> Self {
+        TablebaseBoard {
+            board: self.board.apply_action(action),
+            tablebase: Arc::clone(&self.tablebase),
+        }
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.board.is_terminal()
+    }
+
+    fn evaluate(&self) -> Option<f32> {
+        self.probe().or_else(|| self.board.evaluate())
+    }
+
+    fn hash_key(&self) -> u64 {
+        self.board.hash_key()
+    }
+
+    fn is_quiet(&self) -> bool {
+        self.board.is_quiet()
+    }
+
+    fn is_path_dependent(&self) -> bool {
+        self.board.is_path_dependent()
+    }
+
+    fn probe(&self) -> Option<f32> {
+        // The tablebase does not know the game is already drawn
+        if self.board.moves_without_capture >= 50 || self.board.is_threefold_repetition() {
+            return None;
+        }
+        self.tablebase.score(&self.board)
+    }
+}
+
+impl MakeMove for TablebaseBoard {
+    type Undo = <CheckersBoard as MakeMove>::Undo;
+
+    fn make_move(&mut self, action: &Self::Action) -> Self::Undo {
+        self.board.make_move(action)
+    }
+
+    fn unmake_move(&mut self, undo: Self::Undo) {
+        self.board.unmake_move(undo);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minmax::{TranspositionTable, minmax_in_place};
+    use crate::pdn::parse_fen;
+
+    /// Checks every position of `tablebase` against the values after one
+    /// move, which is what the retrograde analysis must agree with.
+    fn assert_consistent(tablebase: &Tablebase) {
+        for material in materials(tablebase.max_pieces) {
+            for index in 0..2 * material.size() {
+                let Some(board) = position(&material, index) else {
+                    continue;
+                };
+                let children: Vec<_> = board
+                    .get_possible_actions()
+                    .iter()
+                    .map(|action| {
+                        let child = board.apply_action(action);
+                        // Taking the last piece leaves the opponent lost
+                        tablebase.probe(&child).unwrap_or(EndgameValue::Loss(0))
+                    })
+                    .collect();
+                let fastest_win = children
+                    .iter()
+                    .filter_map(|value| match value {
+                        EndgameValue::Loss(plies) => Some(plies + 1),
+                        _ => None,
+                    })
+                    .min();
+                let slowest_loss = children
+                    .iter()
+                    .map(|value| match value {
+                        EndgameValue::Win(plies) => Some(plies + 1),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>()
+                    .and_then(|losses| losses.into_iter().max());
+                let expected = match (fastest_win, slowest_loss) {
+                    (Some(plies), _) => EndgameValue::Win(plies),
+                    (None, Some(plies)) => EndgameValue::Loss(plies),
+                    (None, None) => EndgameValue::Draw,
+                };
+                assert_eq!(tablebase.probe(&board), Some(expected), "\n{:?}", board);
+            }
+        }
+    }
+
+    #[test]
+    fn test_index_round_trip() {
+        let material = Material {
+            men: [1, 1],
+            kings: [1, 0],
+        };
+        let mut positions = 0;
+        for index in 0..2 * material.size() {
+            if let Some(board) = position(&material, index) {
+                assert_eq!(Material::of(&board), material);
+                assert_eq!(self::index(&board, &material), Some(index));
+                positions += 1;
+            }
+        }
+        // Placements of the three pieces on distinct squares
+        assert_eq!(positions, 2 * 22800);
+    }
+
+    #[test]
+    fn test_materials_solve_captures_and_promotions_first() {
+        let materials = materials(3);
+        assert_eq!(materials.len(), 4 + 2 * 6);
+        for (position, material) in materials.iter().enumerate() {
+            for side in 0..2 {
+                let earlier = &materials[..position];
+                if material.men[side] > 0 {
+                    let mut promoted = *material;
+                    promoted.men[side] -= 1;
+                    promoted.kings[side] += 1;
+                    assert!(earlier.contains(&promoted));
+                }
+                if material.pieces(side) > 1 {
+                    let mut captured = *material;
+                    if captured.men[side] > 0 {
+                        captured.men[side] -= 1;
+                    } else {
+                        captured.kings[side] -= 1;
+                    }
+                    assert!(earlier.contains(&captured));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_two_piece_tablebase() {
+        let tablebase = Tablebase::generate(2).unwrap();
+        assert_consistent(&tablebase);
+
+        // A lone king cannot be caught unless it is trapped
+        let board = parse_fen("W:WK1:BK32").unwrap();
+        assert_eq!(tablebase.probe(&board), Some(EndgameValue::Draw));
+        let board = parse_fen("B:W18:B14").unwrap();
+        assert_eq!(tablebase.probe(&board), Some(EndgameValue::Win(1)));
+        assert_eq!(tablebase.probe(&CheckersBoard::new()), None);
+    }
+
+    #[test]
+    fn test_probe_man_on_crowning_row() {
+        let tablebase = Tablebase::generate(2).unwrap();
+        // Men of either colour on their own crowning row are not indexed
+        let mut tiles = [[CheckersTile::Empty; 8]; 8];
+        tiles[7][1] = CheckersTile::Checker(Player::Player1);
+        tiles[4][4] = CheckersTile::Checker(Player::Player2);
+        let board = CheckersBoard::from_board(tiles, Player::Player1, 0);
+        assert_eq!(tablebase.probe(&board), None);
+
+        let mut tiles = [[CheckersTile::Empty; 8]; 8];
+        tiles[0][6] = CheckersTile::Checker(Player::Player2);
+        tiles[3][3] = CheckersTile::Checker(Player::Player1);
+        let board = CheckersBoard::from_board(tiles, Player::Player1, 0);
+        assert_eq!(tablebase.probe(&board), None);
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let tablebase = Tablebase::generate(2).unwrap();
+        let bytes = tablebase.to_bytes();
+        assert_eq!(Tablebase::from_bytes(&bytes).unwrap(), tablebase);
+
+        assert_eq!(
+            Tablebase::from_bytes(b"FRBK\x01\x02").unwrap_err(),
+            TablebaseError::NotATablebase
+        );
+        assert_eq!(
+            Tablebase::from_bytes(b"FRTB\x07\x02").unwrap_err(),
+            TablebaseError::UnsupportedVersion(7)
+        );
+        assert_eq!(
+            Tablebase::from_bytes(b"FRTB\x01\xff").unwrap_err(),
+            TablebaseError::UnsupportedPieces(255)
+        );
+        assert_eq!(
+            Tablebase::from_bytes(&bytes[..100]).unwrap_err(),
+            TablebaseError::WrongLength {
+                expected: bytes.len(),
+                found: 100
+            }
+        );
+    }
+
+    #[test]
+    #[ignore = "slow in debug builds, run with --release --ignored"]
+    fn test_three_piece_tablebase() {
+        assert_consistent(&Tablebase::generate(3).unwrap());
+    }
+
+    #[test]
+    fn test_search_converts_won_endgame() {
+        let tablebase = Arc::new(Tablebase::generate(3).unwrap());
+        // Two kings against one, far apart
+        let mut state = TablebaseBoard {
+            board: parse_fen("W:WK1,K3:BK32").unwrap(),
+            tablebase: Arc::clone(&tablebase),
+        };
+        let Some(EndgameValue::Win(plies)) = tablebase.probe(&state.board) else {
+            panic!("two kings beat one");
+        };
+
+        for _ in 0..plies {
+            let maximizing_player = state.board.current_player() == Player::Player1;
+            let mut table = TranspositionTable::new();
+            let result = minmax_in_place(
+                &state,
+                2,
+                maximizing_player,
+                &mut table,
+                f32::NEG_INFINITY,
+                f32::INFINITY,
+            );
+            let Some(action) = result.best_action else {
+                break;
+            };
+            state.make_move(&action);
+        }
+        assert_eq!(state.board.count_pieces(Player::Player1), 0);
+    }
+}
@@ -0,0 +1,850 @@
+//! Engine-vs-engine matches for the CLI binary
+//!
+//! Two engine configurations play a series of games from a set of opening
+//! positions. Every opening is played twice with the colours swapped, so
+//! neither engine profits from a lopsided opening. Results are counted from
+//! the point of view of the first engine and summarised as an Elo
+//! difference with a 95% confidence interval.
+//!
+//! With a sequential probability ratio test the match stops as soon as the
+//! results are clear enough to accept either hypothesis, instead of always
+//! playing every game.
+
+use std::{
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+
+use fourrow::checkers::{CheckersBoard, CheckersTile};
+use fourrow::four::FourRow;
+use fourrow::mcts::{MctsConfig, mcts};
+use fourrow::minmax::{GameResult, GameState, MakeMove, Player};
+
+use crate::play::{self, Playable, SearchLimit};
+
+/// A named evaluation function that can replace `GameState::evaluate`.
+///
+/// `evaluate` is passed one weight per term it adds up, `weights` unless the
+/// engine spec gives others.
+pub struct Heuristic<T> {
+    pub name: &'static str,
+    pub evaluate: fn(&T, &[f32]) -> Option<f32>,
+    pub weights: &'static [f32],
+}
+
+// Derived impls would require `T: Clone`
+impl<T> Clone for Heuristic<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Heuristic<T> {}
+
+/// A game that tournaments can be played in.
+pub trait Contest: Playable + 'static
+where
+    Self::Action: Send + Sync,
+{
+    /// Evaluation variants to choose from, `default` being the built-in one.
+    fn heuristics() -> &'static [Heuristic<Self>];
+}
+
+impl Contest for FourRow {
+    fn heuristics() -> &'static [Heuristic<Self>] {
+        &[
+            Heuristic {
+                name: "default",
+                evaluate: builtin,
+                weights: &[],
+            },
+            Heuristic {
+                name: "weighted",
+                evaluate: four_weighted,
+                weights: &[1.0, 1.0],
+            },
+            Heuristic {
+                name: "centre",
+                evaluate: four_weighted,
+                weights: &[1.0, 0.0],
+            },
+            Heuristic {
+                name: "threats",
+                evaluate: four_weighted,
+                weights: &[0.0, 1.0],
+            },
+            Heuristic {
+                name: "terminal",
+                evaluate: terminal_only,
+                weights: &[],
+            },
+        ]
+    }
+}
+
+impl Contest for CheckersBoard {
+    fn heuristics() -> &'static [Heuristic<Self>] {
+        &[
+            Heuristic {
+                name: "default",
+                evaluate: builtin,
+                weights: &[],
+            },
+            Heuristic {
+                name: "material",
+                evaluate: checkers_material,
+                weights: &[10.0, 30.0],
+            },
+            Heuristic {
+                name: "terminal",
+                evaluate: terminal_only,
+                weights: &[],
+            },
+        ]
+    }
+}
+
+/// The game's own `GameState::evaluate`.
+fn builtin<T: GameState>(state: &T, _weights: &[f32]) -> Option<f32> {
+    state.evaluate()
+}
+
+/// Knows only won, lost and drawn positions.
+fn terminal_only<T: GameState>(state: &T, _weights: &[f32]) -> Option<f32> {
+    if state.is_terminal() {
+        state.evaluate()
+    } else {
+        Some(0.0)
+    }
+}
+
+/// The centre and open row terms of the default, weighted by the first and
+/// second weight.
+fn four_weighted(state: &FourRow, weights: &[f32]) -> Option<f32> {
+    if state.is_terminal() {
+        return state.evaluate();
+    }
+    let (centre, threats) = (weights[0], weights[1]);
+    Some(centre * state.centre_control() + threats * state.count_open_rows())
+}
+
+/// Counts pieces only, without the positional terms of the default. Men are
+/// worth the first weight and kings the second.
+fn checkers_material(state: &CheckersBoard, weights: &[f32]) -> Option<f32> {
+    if state.is_terminal() {
+        return state.evaluate();
+    }
+    let (man, king) = (weights[0], weights[1]);
+    let score = state
+        .board()
+        .iter()
+        .flatten()
+        .map(|tile| match tile {
+            CheckersTile::Checker(Player::Player1) => man,
+            CheckersTile::Checker(Player::Player2) => -man,
+            CheckersTile::Queen(Player::Player1) => king,
+            CheckersTile::Queen(Player::Player2) => -king,
+            CheckersTile::Empty => 0.0,
+        })
+        .sum();
+    Some(score)
+}
+
+/// A position that is evaluated with a chosen heuristic.
+///
+/// Equality and hashing only look at the position, the heuristic is the same
+/// for every position in one search.
+#[derive(Clone)]
+struct Scored<T> {
+    state: T,
+    evaluate: fn(&T, &[f32]) -> Option<f32>,
+    weights: Arc<[f32]>,
+}
+
+impl<T: PartialEq> PartialEq for Scored<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.state == other.state
+    }
+}
+
+impl<T: Eq> Eq for Scored<T> {}
+
+impl<T: Hash> Hash for Scored<T> {
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        self.state.hash(hasher);
+    }
+}
+
+impl<T: Debug> Debug for Scored<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.state.fmt(f)
+    }
+}
+
+impl<T: GameState> GameState for Scored<T> {
+    type Action = T::Action;
+
+    fn get_possible_actions(&self) -> Vec<Self::Action> {
+        self.state.get_possible_actions()
+    }
+
+    fn apply_action(&self, action: &Self::Action) -> Self {
+        Scored {
+            state: self.state.apply_action(action),
+            evaluate: self.evaluate,
+            weights: Arc::clone(&self.weights),
+        }
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.state.is_terminal()
+    }
+
+    fn evaluate(&self) -> Option<f32> {
+        (self.evaluate)(&self.state, &self.weights)
+    }
+
+    fn hash_key(&self) -> u64 {
+        self.state.hash_key()
+    }
+
+    fn is_quiet(&self) -> bool {
+        self.state.is_quiet()
+    }
+
+    fn probe(&self) -> Option<f32> {
+        self.state.probe()
+    }
+
+    fn is_path_dependent(&self) -> bool {
+        self.state.is_path_dependent()
+    }
+}
+
+impl<T: MakeMove> MakeMove for Scored<T> {
+    type Undo = T::Undo;
+
+    fn make_move(&mut self, action: &Self::Action) -> T::Undo {
+        self.state.make_move(action)
+    }
+
+    fn unmake_move(&mut self, undo: T::Undo) {
+        self.state.unmake_move(undo);
+    }
+}
+
+impl<T: Playable> Playable for Scored<T>
+where
+    T::Action: Send + Sync,
+{
+    fn side_to_move(&self) -> Player {
+        self.state.side_to_move()
+    }
+
+    fn parse_move(&self, text: &str, ply: usize) -> Result<Self::Action, String> {
+        self.state.parse_move(text, ply)
+    }
+
+    fn format_move(&self, action: &Self::Action) -> String {
+        self.state.format_move(action)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Algorithm {
+    Minmax(SearchLimit),
+    Mcts(MctsConfig),
+}
+
+/// Engine configuration in the form `key=value,...`.
+///
+/// Keys are `depth`, `time` (seconds per move), `iterations` (MCTS
+/// playouts), `eval` (heuristic name), `weights` (colon separated weights
+/// of the heuristic's terms) and the flag `mcts`. The default is a depth 6
+/// alpha-beta search with the default heuristic.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EngineSpec {
+    pub algorithm: Algorithm,
+    pub heuristic: String,
+    /// Replaces the heuristic's own weights if set.
+    pub weights: Option<Vec<f32>>,
+}
+
+impl FromStr for EngineSpec {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut use_mcts = false;
+        let mut depth = None;
+        let mut time = None;
+        let mut iterations = None;
+        let mut heuristic = "default".to_string();
+        let mut weights = None;
+
+        for part in spec.split(',').filter(|part| !part.is_empty()) {
+            let (key, value) = part.split_once('=').unwrap_or((part, ""));
+            let invalid = || format!("invalid value '{}' for '{}'", value, key);
+            match key {
+                "mcts" if value.is_empty() => use_mcts = true,
+                "depth" => {
+                    depth = Some(
+                        value
+                            .parse::<u8>()
+                            .ok()
+                            .filter(|&depth| depth > 0)
+                            .ok_or_else(invalid)?,
+                    )
+                }
+                "time" => {
+                    time = Some(
+                        value
+                            .parse::<f64>()
+                            .ok()
+                            .filter(|&seconds| seconds > 0.0 && seconds.is_finite())
+                            .map(Duration::from_secs_f64)
+                            .ok_or_else(invalid)?,
+                    )
+                }
+                "iterations" => {
+                    iterations = Some(
+                        value
+                            .parse::<u32>()
+                            .ok()
+                            .filter(|&iterations| iterations > 0)
+                            .ok_or_else(invalid)?,
+                    )
+                }
+                "eval" if !value.is_empty() => heuristic = value.to_string(),
+                "weights" => {
+                    weights = Some(
+                        value
+                            .split(':')
+                            .map(|weight| weight.parse::<f32>().ok().filter(|w| w.is_finite()))
+                            .collect::<Option<Vec<_>>>()
+                            .ok_or_else(invalid)?,
+                    )
+                }
+                _ => return Err(format!("unknown engine setting '{}'", part)),
+            }
+        }
+
+        let algorithm = if use_mcts {
+            if depth.is_some() {
+                return Err("depth does not apply to mcts".to_string());
+            }
+            let mut config = MctsConfig {
+                time_budget: time,
+                ..MctsConfig::default()
+            };
+            match (time, iterations) {
+                (Some(_), None) => config.max_iterations = u32::MAX,
+                (_, Some(iterations)) => config.max_iterations = iterations,
+                (None, None) => {}
+            }
+            Algorithm::Mcts(config)
+        } else {
+            if iterations.is_some() {
+                return Err("iterations only apply to mcts".to_string());
+            }
+            match (depth, time) {
+                (Some(_), Some(_)) => return Err("give either depth or time".to_string()),
+                (_, Some(time)) => Algorithm::Minmax(SearchLimit::Time(time)),
+                (depth, None) => Algorithm::Minmax(SearchLimit::Depth(depth.unwrap_or(6))),
+            }
+        };
+
+        Ok(EngineSpec {
+            algorithm,
+            heuristic,
+            weights,
+        })
+    }
+}
+
+struct Engine<T> {
+    algorithm: Algorithm,
+    heuristic: Heuristic<T>,
+    weights: Arc<[f32]>,
+}
+
+impl<T: Contest> Engine<T>
+where
+    T::Action: Send + Sync,
+{
+    fn new(spec: &EngineSpec) -> Result<Self, String> {
+        let heuristic = T::heuristics()
+            .iter()
+            .find(|heuristic| heuristic.name == spec.heuristic)
+            .copied()
+            .ok_or_else(|| {
+                let names: Vec<_> = T::heuristics().iter().map(|h| h.name).collect();
+                format!(
+                    "unknown heuristic '{}', expected one of {}",
+                    spec.heuristic,
+                    names.join(", ")
+                )
+            })?;
+        let weights = match &spec.weights {
+            Some(weights) if weights.len() != heuristic.weights.len() => {
+                return Err(format!(
+                    "heuristic '{}' takes {} weights, got {}",
+                    heuristic.name,
+                    heuristic.weights.len(),
+                    weights.len()
+                ));
+            }
+            Some(weights) => weights.as_slice(),
+            None => heuristic.weights,
+        };
+        Ok(Engine {
+            algorithm: spec.algorithm,
+            heuristic,
+            weights: weights.into(),
+        })
+    }
+
+    fn choose(&self, state: &T, seed: u64) -> T::Action {
+        let scored = Scored {
+            state: state.clone(),
+            evaluate: self.heuristic.evaluate,
+            weights: Arc::clone(&self.weights),
+        };
+        let result = match self.algorithm {
+            Algorithm::Minmax(limit) => play::search(&scored, limit),
+            Algorithm::Mcts(config) => {
+                let config = MctsConfig {
+                    seed: config.seed ^ seed,
+                    ..config
+                };
+                mcts(&scored, state.side_to_move() == Player::Player1, &config)
+            }
+        };
+        result.best_action.expect("non-terminal position has moves")
+    }
+}
+
+/// Every position reachable from `start` in exactly `plies` plies, without
+/// duplicates or finished games, in move generation order.
+pub fn openings<T: GameState + Clone + PartialEq>(start: &T, plies: usize) -> Vec<T> {
+    let mut positions = vec![start.clone()];
+    for _ in 0..plies {
+        let mut next = Vec::new();
+        for position in &positions {
+            for action in position.get_possible_actions() {
+                let child = position.apply_action(&action);
+                if !child.is_terminal() && !next.contains(&child) {
+                    next.push(child);
+                }
+            }
+        }
+        positions = next;
+    }
+    positions
+}
+
+/// Wins, draws and losses of the first engine.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Score {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Score {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Average points per game, a draw counting half.
+    pub fn ratio(&self) -> f64 {
+        moments(self.wins as f64, self.draws as f64, self.losses as f64).0
+    }
+
+    /// Elo difference and the half-width of its 95% confidence interval,
+    /// infinite while one engine has scored every point.
+    pub fn elo(&self) -> (f64, f64) {
+        let (ratio, variance) = moments(self.wins as f64, self.draws as f64, self.losses as f64);
+        let elo = elo_from_ratio(ratio);
+        if !elo.is_finite() {
+            return (elo, f64::INFINITY);
+        }
+        let margin = 1.96 * (variance / self.games() as f64).sqrt();
+        let low = elo_from_ratio((ratio - margin).max(0.0));
+        let high = elo_from_ratio((ratio + margin).min(1.0));
+        (elo, (high - low) / 2.0)
+    }
+
+    /// Log-likelihood ratio of `elo1` against `elo0`, using the normal
+    /// approximation of the generalised SPRT.
+    ///
+    /// Half a game of each result is added so that the variance is never
+    /// zero, otherwise a clean sweep would never end the test.
+    pub fn llr(&self, elo0: f64, elo1: f64) -> f64 {
+        let (wins, draws, losses) = (
+            self.wins as f64 + 0.5,
+            self.draws as f64 + 0.5,
+            self.losses as f64 + 0.5,
+        );
+        let (ratio, variance) = moments(wins, draws, losses);
+        let (score0, score1) = (ratio_from_elo(elo0), ratio_from_elo(elo1));
+        (wins + draws + losses) * (score1 - score0) * (2.0 * ratio - score0 - score1)
+            / (2.0 * variance)
+    }
+}
+
+/// Mean and variance of the points of a single game.
+fn moments(wins: f64, draws: f64, losses: f64) -> (f64, f64) {
+    let games = (wins + draws + losses).max(1.0);
+    let mean = (wins + 0.5 * draws) / games;
+    let variance =
+        (wins * (1.0 - mean).powi(2) + draws * (0.5 - mean).powi(2) + losses * mean.powi(2))
+            / games;
+    (mean, variance)
+}
+
+fn elo_from_ratio(ratio: f64) -> f64 {
+    -400.0 * (1.0 / ratio - 1.0).log10()
+}
+
+fn ratio_from_elo(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+/// Sequential probability ratio test of H0: the Elo difference is `elo0`
+/// against H1: it is `elo1`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    /// Probability of accepting H1 although H0 is true.
+    pub alpha: f64,
+    /// Probability of accepting H0 although H1 is true.
+    pub beta: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SprtResult {
+    AcceptH0,
+    AcceptH1,
+    Continue,
+}
+
+impl Sprt {
+    pub fn new(elo0: f64, elo1: f64) -> Self {
+        Sprt {
+            elo0,
+            elo1,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+
+    /// Lower and upper stopping bounds of the log-likelihood ratio.
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    pub fn test(&self, score: &Score) -> SprtResult {
+        let llr = score.llr(self.elo0, self.elo1);
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            SprtResult::AcceptH1
+        } else if llr <= lower {
+            SprtResult::AcceptH0
+        } else {
+            SprtResult::Continue
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TournamentConfig {
+    pub engines: [EngineSpec; 2],
+    /// Maximum number of games, rounded up to an even number.
+    pub games: u32,
+    /// Openings are all positions this many plies from the start.
+    pub opening_plies: usize,
+    pub sprt: Option<Sprt>,
+}
+
+/// Plays the match and prints progress and the final result.
+pub fn run<T: Contest>(start: &T, config: &TournamentConfig) -> Result<Score, String>
+where
+    T::Action: Send + Sync,
+{
+    let engines = [
+        Engine::<T>::new(&config.engines[0])?,
+        Engine::<T>::new(&config.engines[1])?,
+    ];
+    let openings = openings(start, config.opening_plies);
+    if openings.is_empty() {
+        return Err(format!(
+            "no openings {} plies from the start",
+            config.opening_plies
+        ));
+    }
+    println!(
+        "{} openings, up to {} games",
+        openings.len(),
+        config.games.div_ceil(2) * 2
+    );
+
+    let mut score = Score::default();
+    for game in 0..config.games.div_ceil(2) * 2 {
+        let opening = &openings[(game / 2) as usize % openings.len()];
+        // The first engine plays Player1 in even games
+        let first_is_player1 = game % 2 == 0;
+        let players = if first_is_player1 {
+            [&engines[0], &engines[1]]
+        } else {
+            [&engines[1], &engines[0]]
+        };
+
+        let first = if first_is_player1 {
+            Player::Player1
+        } else {
+            Player::Player2
+        };
+        let outcome = match play_game(opening, players, game as u64) {
+            GameResult::Win(winner) if winner == first => {
+                score.wins += 1;
+                "engine1 wins"
+            }
+            GameResult::Win(_) => {
+                score.losses += 1;
+                "engine2 wins"
+            }
+            _ => {
+                score.draws += 1;
+                "draw"
+            }
+        };
+
+        let (elo, margin) = score.elo();
+        println!(
+            "Game {}: {}  W-D-L {}-{}-{}  Elo {:+.1} +/- {:.1}",
+            game + 1,
+            outcome,
+            score.wins,
+            score.draws,
+            score.losses,
+            elo,
+            margin
+        );
+
+        if let Some(sprt) = &config.sprt {
+            // Stop only after complete pairs so both colours count equally
+            if game % 2 == 1 && sprt.test(&score) != SprtResult::Continue {
+                break;
+            }
+        }
+    }
+
+    report(&score, config.sprt.as_ref());
+    Ok(score)
+}
+
+/// Plays one game with `players` as Player1 and Player2.
+fn play_game<T: Contest>(start: &T, players: [&Engine<T>; 2], seed: u64) -> GameResult
+where
+    T::Action: Send + Sync,
+{
+    let mut state = start.clone();
+    let mut ply = 0;
+    while !state.is_terminal() {
+        let engine = match state.side_to_move() {
+            Player::Player1 => players[0],
+            Player::Player2 => players[1],
+        };
+        let action = engine.choose(&state, seed.wrapping_mul(1_000_003).wrapping_add(ply));
+        state = state.apply_action(&action);
+        ply += 1;
+    }
+    match state.evaluate() {
+        Some(evaluation) if evaluation > 0.0 => GameResult::Win(Player::Player1),
+        Some(evaluation) if evaluation < 0.0 => GameResult::Win(Player::Player2),
+        _ => GameResult::Draw,
+    }
+}
+
+fn report(score: &Score, sprt: Option<&Sprt>) {
+    let (elo, margin) = score.elo();
+    println!();
+    println!(
+        "Games: {}  Wins: {}  Draws: {}  Losses: {}  Score: {:.1}%",
+        score.games(),
+        score.wins,
+        score.draws,
+        score.losses,
+        100.0 * score.ratio()
+    );
+    println!("Elo difference: {:+.1} +/- {:.1} (95%)", elo, margin);
+    if let Some(sprt) = sprt {
+        let (lower, upper) = sprt.bounds();
+        let verdict = match sprt.test(score) {
+            SprtResult::AcceptH0 => "H0 accepted",
+            SprtResult::AcceptH1 => "H1 accepted",
+            SprtResult::Continue => "inconclusive",
+        };
+        println!(
+            "SPRT elo0={} elo1={}: LLR {:.2} ({:.2}, {:.2}), {}",
+            sprt.elo0,
+            sprt.elo1,
+            score.llr(sprt.elo0, sprt.elo1),
+            lower,
+            upper,
+            verdict
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(wins: u32, draws: u32, losses: u32) -> Score {
+        Score {
+            wins,
+            draws,
+            losses,
+        }
+    }
+
+    #[test]
+    fn test_engine_spec() {
+        assert_eq!(
+            "".parse(),
+            Ok(EngineSpec {
+                algorithm: Algorithm::Minmax(SearchLimit::Depth(6)),
+                heuristic: "default".to_string(),
+                weights: None,
+            })
+        );
+        let spec: EngineSpec = "time=0.5,eval=material".parse().unwrap();
+        assert_eq!(
+            spec.algorithm,
+            Algorithm::Minmax(SearchLimit::Time(Duration::from_millis(500)))
+        );
+        assert_eq!(spec.heuristic, "material");
+        assert_eq!(spec.weights, None);
+
+        let spec: EngineSpec = "eval=weighted,weights=2:0.5".parse().unwrap();
+        assert_eq!(spec.weights, Some(vec![2.0, 0.5]));
+        assert_eq!(
+            "weights=1:x".parse::<EngineSpec>(),
+            Err("invalid value '1:x' for 'weights'".to_string())
+        );
+
+        let Ok(EngineSpec {
+            algorithm: Algorithm::Mcts(config),
+            ..
+        }) = "mcts,iterations=300".parse()
+        else {
+            panic!("expected an mcts engine");
+        };
+        assert_eq!(config.max_iterations, 300);
+
+        assert_eq!(
+            "depth=x".parse::<EngineSpec>(),
+            Err("invalid value 'x' for 'depth'".to_string())
+        );
+        assert_eq!(
+            "mcts,depth=3".parse::<EngineSpec>(),
+            Err("depth does not apply to mcts".to_string())
+        );
+        assert_eq!(
+            "fast".parse::<EngineSpec>(),
+            Err("unknown engine setting 'fast'".to_string())
+        );
+    }
+
+    #[test]
+    fn test_unknown_heuristic() {
+        let spec: EngineSpec = "eval=material".parse().unwrap();
+        assert!(Engine::<CheckersBoard>::new(&spec).is_ok());
+        assert_eq!(
+            Engine::<FourRow>::new(&spec).err(),
+            Some(
+                "unknown heuristic 'material', expected one of default, weighted, centre, \
+                 threats, terminal"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_heuristic_weights() {
+        let spec: EngineSpec = "eval=weighted,weights=1:2:3".parse().unwrap();
+        assert_eq!(
+            Engine::<FourRow>::new(&spec).err(),
+            Some("heuristic 'weighted' takes 2 weights, got 3".to_string())
+        );
+
+        // Player1 has an open row of three on the bottom row
+        let state = FourRow::from_moves("44556").unwrap();
+        assert!(state.count_open_rows() > 0.0);
+        let evaluate = |spec: &str| {
+            let engine = Engine::<FourRow>::new(&spec.parse().unwrap()).unwrap();
+            (engine.heuristic.evaluate)(&state, &engine.weights).unwrap()
+        };
+        assert_eq!(evaluate("eval=weighted"), state.evaluate().unwrap());
+        assert_eq!(evaluate("eval=centre"), state.centre_control());
+        assert_eq!(evaluate("eval=threats"), state.count_open_rows());
+        assert_eq!(
+            evaluate("eval=weighted,weights=2:0"),
+            2.0 * state.centre_control()
+        );
+    }
+
+    #[test]
+    fn test_openings() {
+        assert_eq!(openings(&FourRow::new(), 0), vec![FourRow::new()]);
+        assert_eq!(openings(&FourRow::new(), 2).len(), 49);
+        // Different move orders reaching the same position count once
+        assert_eq!(openings(&FourRow::new(), 3).len(), 238);
+        assert_eq!(openings(&CheckersBoard::new(), 2).len(), 49);
+    }
+
+    #[test]
+    fn test_elo() {
+        assert_eq!(score(5, 0, 5).elo().0, 0.0);
+        let (elo, margin) = score(60, 30, 10).elo();
+        assert!((elo - 190.85).abs() < 0.01, "{}", elo);
+        assert!(margin > 0.0 && margin < elo);
+        let (elo, _) = score(10, 30, 60).elo();
+        assert!((elo + 190.85).abs() < 0.01);
+        assert_eq!(score(3, 0, 0).elo(), (f64::INFINITY, f64::INFINITY));
+    }
+
+    #[test]
+    fn test_sprt() {
+        let sprt = Sprt::new(0.0, 50.0);
+        let (lower, upper) = sprt.bounds();
+        assert!((upper - 2.944).abs() < 0.001);
+        assert!((lower + 2.944).abs() < 0.001);
+
+        assert_eq!(sprt.test(&score(2, 1, 1)), SprtResult::Continue);
+        assert_eq!(sprt.test(&score(150, 50, 50)), SprtResult::AcceptH1);
+        assert_eq!(sprt.test(&score(50, 50, 150)), SprtResult::AcceptH0);
+        assert_eq!(sprt.test(&score(20, 0, 0)), SprtResult::AcceptH1);
+    }
+
+    #[test]
+    fn test_match_alternates_colours() {
+        let config = TournamentConfig {
+            engines: ["depth=4".parse().unwrap(), "depth=1".parse().unwrap()],
+            games: 3,
+            opening_plies: 1,
+            sprt: None,
+        };
+        let score = run(&FourRow::new(), &config).unwrap();
+        assert_eq!(score.games(), 4);
+        assert!(score.ratio() > 0.5);
+    }
+}
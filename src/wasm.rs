@@ -0,0 +1,1216 @@
+//! JavaScript bindings for the web pages
+//!
+//! `ConnectFourGame` and `CheckersGame` wrap the engine for the browser:
+//! they keep the position and its undo history, check the moves coming from
+//! the page and run the AI. Built with the `wasm` feature.
+
+use crate::book::{self, BookChoice, OpeningBook};
+use crate::history::History;
+use crate::minmax::{GameResult, GameState, MakeMove, Player, Tile};
+use crate::tablebase::{Tablebase, TablebaseBoard};
+use crate::{checkers, four, four_bitboard, mcts, minmax, pdn, solver};
+use serde::Serialize;
+use std::cell::Cell;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::{JsFuture, future_to_promise};
+use web_sys::console;
+
+// A macro to provide `println!(..)`-style syntax for `console.log` logging.
+macro_rules! log {
+    ( $( $t:tt )* ) => {
+        console::log_1(&format!( $( $t )* ).into());
+    }
+}
+
+/// Upper bound on the depth reached by the time-limited searches.
+const MAX_SEARCH_DEPTH: u8 = 64;
+
+/// How long the async searches run before letting the browser render and
+/// handle input.
+const AI_SLICE: Duration = Duration::from_millis(30);
+
+/// Playouts the async MCTS searches run before yielding to the browser.
+const MCTS_SLICE: u32 = 200;
+
+/// Search algorithm behind `get_ai_move` and `get_ai_move_timed`.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AiBackend {
+    /// Alpha-beta search guided by the game's evaluation heuristic.
+    Minmax,
+    /// Monte Carlo tree search with random playouts. Its evaluations range
+    /// from -1 to 1.
+    Mcts,
+}
+
+/// Whether the AI plays from the opening book before searching.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookMode {
+    /// Always search.
+    Off,
+    /// Play the best book move.
+    Best,
+    /// Play a book move at random in proportion to its weight, so games
+    /// vary.
+    Weighted,
+}
+
+/// Draughts rules a `CheckersGame` is played by.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    /// English checkers, the default.
+    English,
+    /// Russian draughts, with flying kings.
+    Russian,
+    /// Brazilian draughts, with flying kings and the majority rule.
+    Brazilian,
+    /// American pool checkers, with flying kings.
+    Pool,
+    /// Italian draughts, with the majority and king priority rules.
+    Italian,
+}
+
+impl Variant {
+    fn rules(self) -> checkers::RulesConfig {
+        match self {
+            Variant::English => checkers::RulesConfig::ENGLISH,
+            Variant::Russian => checkers::RulesConfig::RUSSIAN,
+            Variant::Brazilian => checkers::RulesConfig::BRAZILIAN,
+            Variant::Pool => checkers::RulesConfig::POOL,
+            Variant::Italian => checkers::RulesConfig::ITALIAN,
+        }
+    }
+}
+
+/// The AI backend chosen for a game and the options of the MCTS backend.
+#[derive(Debug, Clone, Copy)]
+struct AiSettings {
+    backend: AiBackend,
+    mcts: mcts::MctsConfig,
+    book: BookMode,
+}
+
+impl Default for AiSettings {
+    fn default() -> Self {
+        AiSettings {
+            backend: AiBackend::Minmax,
+            mcts: mcts::MctsConfig::default(),
+            book: BookMode::Best,
+        }
+    }
+}
+
+impl AiSettings {
+    /// A move from `book` for `state`, or `None` if the book is off or the
+    /// position is not in it.
+    fn book_move<T: GameState>(&self, book: &OpeningBook, state: &T) -> Option<T::Action> {
+        let choice = match self.book {
+            BookMode::Off => return None,
+            BookMode::Best => BookChoice::Best,
+            BookMode::Weighted => {
+                BookChoice::Weighted((js_sys::Math::random() * (1u64 << 53) as f64) as u64)
+            }
+        };
+        book.choose(state, choice)
+    }
+
+    /// Minmax searches to `depth`, MCTS runs its configured number of
+    /// iterations instead.
+    fn search<T: MakeMove + Hash + Eq + Clone + Debug>(
+        &self,
+        state: &T,
+        depth: u8,
+        maximizing_player: bool,
+    ) -> minmax::SearchResult<T::Action> {
+        match self.backend {
+            AiBackend::Minmax => {
+                let mut table = minmax::TranspositionTable::new();
+                minmax::minmax_in_place(
+                    state,
+                    depth,
+                    maximizing_player,
+                    &mut table,
+                    f32::NEG_INFINITY,
+                    f32::INFINITY,
+                )
+            }
+            AiBackend::Mcts => mcts::mcts(state, maximizing_player, &self.mcts),
+        }
+    }
+
+    /// Searches until `budget` runs out: iterative deepening for minmax and
+    /// as many playouts as fit for MCTS.
+    fn search_timed<T: MakeMove + Hash + Eq + Clone + Debug>(
+        &self,
+        state: &T,
+        budget: Duration,
+        maximizing_player: bool,
+    ) -> minmax::SearchResult<T::Action> {
+        match self.backend {
+            AiBackend::Minmax => {
+                minmax::iterative_deepening(state, maximizing_player, budget, MAX_SEARCH_DEPTH)
+            }
+            AiBackend::Mcts => {
+                let config = mcts::MctsConfig {
+                    max_iterations: u32::MAX,
+                    time_budget: Some(budget),
+                    ..self.mcts
+                };
+                mcts::mcts(state, maximizing_player, &config)
+            }
+        }
+    }
+}
+
+/// Argument of the `on_progress` callback of the async searches.
+#[derive(Serialize)]
+struct AiProgress<M> {
+    depth: u8,
+    best_move: Option<M>,
+    evaluation: f32,
+}
+
+/// One move returned by `analyze`, in the same form as the game's other
+/// move-taking methods.
+#[derive(Serialize)]
+struct MoveHint<M> {
+    #[serde(rename = "move")]
+    action: M,
+    evaluation: f32,
+    principal_variation: Vec<M>,
+}
+
+/// Converts the result of `minmax::analyze` to an array of `MoveHint`s.
+fn move_hints<A, M: Serialize>(
+    analysis: Vec<minmax::MoveAnalysis<A>>,
+    to_move: impl Fn(&A) -> M,
+) -> Result<JsValue, JsValue> {
+    let hints: Vec<_> = analysis
+        .into_iter()
+        .map(|entry| MoveHint {
+            action: to_move(&entry.action),
+            evaluation: entry.evaluation,
+            principal_variation: entry.principal_variation.iter().map(&to_move).collect(),
+        })
+        .collect();
+    Ok(serde_wasm_bindgen::to_value(&hints)?)
+}
+
+/// Resolves on the next turn of the browser's event loop, after pending
+/// input and rendering. Outside a window, e.g. in a worker, it resolves at
+/// once.
+async fn yield_to_browser() {
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+        let scheduled = web_sys::window()
+            .is_some_and(|window| window.set_timeout_with_callback(&resolve).is_ok());
+        if !scheduled {
+            let _ = resolve.call0(&JsValue::UNDEFINED);
+        }
+    });
+    let _ = JsFuture::from(promise).await;
+}
+
+/// Minmax search to `depth` in `AI_SLICE` slices, yielding to the browser
+/// between them. Every completed depth is passed to `on_progress` with the
+/// best move converted by `to_move`. Fails once `cancelled` is set.
+async fn search_in_slices<T: MakeMove + Clone, M: Serialize>(
+    state: T,
+    depth: u8,
+    maximizing_player: bool,
+    on_progress: Option<js_sys::Function>,
+    cancelled: Rc<Cell<bool>>,
+    to_move: impl Fn(&T::Action) -> M,
+) -> Result<minmax::SearchResult<T::Action>, JsValue> {
+    let mut search = minmax::SlicedSearch::new(&state, maximizing_player, depth);
+    let mut reported_depth = 0;
+    loop {
+        if cancelled.get() {
+            return Err(JsValue::from_str("search cancelled"));
+        }
+        let finished = search.step(AI_SLICE);
+
+        if let (Some(callback), Some(result)) = (&on_progress, search.result())
+            && result.depth > reported_depth
+        {
+            reported_depth = result.depth;
+            let progress = AiProgress {
+                depth: result.depth,
+                best_move: result.best_action.as_ref().map(&to_move),
+                evaluation: result.evaluation,
+            };
+            callback.call1(&JsValue::NULL, &serde_wasm_bindgen::to_value(&progress)?)?;
+        }
+        if finished {
+            return Ok(search
+                .result()
+                .cloned()
+                .expect("a finished search has a result"));
+        }
+        yield_to_browser().await;
+    }
+}
+
+/// MCTS search in slices of `MCTS_SLICE` playouts, yielding to the browser
+/// between them. The current best move is passed to `on_progress` after
+/// every slice, converted by `to_move`. Fails once `cancelled` is set.
+async fn mcts_in_slices<T: GameState + Clone, M: Serialize>(
+    state: T,
+    maximizing_player: bool,
+    config: mcts::MctsConfig,
+    on_progress: Option<js_sys::Function>,
+    cancelled: Rc<Cell<bool>>,
+    to_move: impl Fn(&T::Action) -> M,
+) -> Result<minmax::SearchResult<T::Action>, JsValue> {
+    let mut search = mcts::SlicedSearch::new(&state, maximizing_player, &config);
+    loop {
+        if cancelled.get() {
+            return Err(JsValue::from_str("search cancelled"));
+        }
+        let finished = search.step(MCTS_SLICE);
+
+        if let Some(callback) = &on_progress {
+            let result = search.result();
+            let progress = AiProgress {
+                depth: result.depth,
+                best_move: result.best_action.as_ref().map(&to_move),
+                evaluation: result.evaluation,
+            };
+            callback.call1(&JsValue::NULL, &serde_wasm_bindgen::to_value(&progress)?)?;
+        }
+        if finished {
+            return Ok(search.result());
+        }
+        yield_to_browser().await;
+    }
+}
+
+#[wasm_bindgen]
+pub struct ConnectFourGame {
+    state: four::FourRow,
+    // Shared with async searches, which set it when they finish.
+    last_ai_evaluation: Rc<Cell<Option<f32>>>,
+    history: History<(four::FourRow, Option<f32>)>,
+    // Created on first use, its transposition table is large.
+    solver: Option<solver::Solver>,
+    ai: AiSettings,
+    // Set to stop the running async search, replaced for every new one.
+    ai_cancelled: Rc<Cell<bool>>,
+}
+
+#[wasm_bindgen]
+impl ConnectFourGame {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> ConnectFourGame {
+        Self::start_from(four::FourRow::new())
+    }
+
+    /// A game on a board of another size, e.g. 8x7 or connect five with
+    /// `win_length` 5. Boards can be at most 9x9.
+    #[wasm_bindgen]
+    pub fn with_size(
+        width: usize,
+        height: usize,
+        win_length: usize,
+    ) -> Result<ConnectFourGame, JsValue> {
+        let config = four::BoardConfig::new(width, height, win_length).ok_or_else(|| {
+            JsValue::from_str(&format!(
+                "unsupported board: {}x{} with {} in a row",
+                width, height, win_length
+            ))
+        })?;
+        Ok(Self::start_from(four::FourRow::with_config(config)))
+    }
+
+    fn start_from(state: four::FourRow) -> ConnectFourGame {
+        ConnectFourGame {
+            state: state.clone(),
+            last_ai_evaluation: Rc::default(),
+            history: History::new((state, None)),
+            solver: None,
+            ai: AiSettings::default(),
+            ai_cancelled: Rc::default(),
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn get_board(&self) -> String {
+        let config = self.state.config;
+        let board: Vec<&[Tile]> = self.state.board[..config.height]
+            .iter()
+            .map(|row| &row[..config.width])
+            .collect();
+        serde_json::to_string(&board).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    #[wasm_bindgen]
+    pub fn get_current_player(&self) -> u8 {
+        match self.state.current_player {
+            Player::Player1 => 1,
+            Player::Player2 => 2,
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn get_possible_moves(&self) -> Vec<u8> {
+        self.state
+            .get_possible_actions()
+            .iter()
+            .map(|a| a.column)
+            .collect()
+    }
+
+    #[wasm_bindgen]
+    pub fn make_move(&mut self, column: u8) -> bool {
+        let action = four::GameAction { column };
+        if self.state.get_possible_actions().contains(&action) {
+            self.state = self.state.apply_action(&action);
+            self.history
+                .push((self.state.clone(), self.last_ai_evaluation.get()));
+            true
+        } else {
+            false
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn get_game_result(&self) -> u8 {
+        match self.state.get_terminal() {
+            GameResult::Win(Player::Player1) => 1,
+            GameResult::Win(Player::Player2) => 2,
+            GameResult::Draw => 3,
+            GameResult::Ongoing => 0,
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn get_ai_move(&mut self, depth: u8, ai_is_player1: bool) -> Option<u8> {
+        if let Some(action) = self.book_move() {
+            return self.choose_book_move(action);
+        }
+        log!("AI is thinking with depth {}...", depth);
+
+        let maximizing_player = ai_is_player1;
+        let result = match self.search_state() {
+            Some(bitboard) => self.ai.search(&bitboard, depth, maximizing_player),
+            None => self.ai.search(&self.state, depth, maximizing_player),
+        };
+
+        self.choose_move(&result)
+    }
+
+    /// Like `get_ai_move`, but searches ever deeper until `time_ms`
+    /// milliseconds have passed instead of to a fixed depth.
+    #[wasm_bindgen]
+    pub fn get_ai_move_timed(&mut self, time_ms: u32, ai_is_player1: bool) -> Option<u8> {
+        if let Some(action) = self.book_move() {
+            return self.choose_book_move(action);
+        }
+        log!("AI is thinking for {} ms...", time_ms);
+
+        let maximizing_player = ai_is_player1;
+        let budget = Duration::from_millis(time_ms as u64);
+        let result = match self.search_state() {
+            Some(bitboard) => self.ai.search_timed(&bitboard, budget, maximizing_player),
+            None => self.ai.search_timed(&self.state, budget, maximizing_player),
+        };
+        log!("AI reached depth {}", result.depth);
+
+        self.choose_move(&result)
+    }
+
+    /// Like `get_ai_move`, but returns a `Promise` and searches in short
+    /// slices so the page stays responsive. `on_progress` is called with
+    /// `{ depth, best_move, evaluation }` after every completed depth, or
+    /// with the current best move after every slice of MCTS playouts.
+    ///
+    /// The promise resolves to the column, or `undefined` if there is no
+    /// move, and rejects if `cancel_ai` or `reset` is called first.
+    #[wasm_bindgen]
+    pub fn get_ai_move_async(
+        &mut self,
+        depth: u8,
+        ai_is_player1: bool,
+        on_progress: Option<js_sys::Function>,
+    ) -> js_sys::Promise {
+        let cancelled = self.start_async_search();
+        if let Some(action) = self.book_move() {
+            let value = self
+                .choose_book_move(action)
+                .map_or(JsValue::UNDEFINED, JsValue::from);
+            return js_sys::Promise::resolve(&value);
+        }
+        log!("AI is thinking with depth {}...", depth);
+
+        let last_ai_evaluation = Rc::clone(&self.last_ai_evaluation);
+        let (ai, state, bitboard) = (self.ai, self.state.clone(), self.search_state());
+        let column = |action: &four::GameAction| action.column;
+        future_to_promise(async move {
+            let result = match (ai.backend, bitboard) {
+                (AiBackend::Mcts, Some(bitboard)) => {
+                    mcts_in_slices(
+                        bitboard,
+                        ai_is_player1,
+                        ai.mcts,
+                        on_progress,
+                        cancelled,
+                        column,
+                    )
+                    .await?
+                }
+                (AiBackend::Mcts, None) => {
+                    mcts_in_slices(
+                        state,
+                        ai_is_player1,
+                        ai.mcts,
+                        on_progress,
+                        cancelled,
+                        column,
+                    )
+                    .await?
+                }
+                (AiBackend::Minmax, Some(bitboard)) => {
+                    search_in_slices(
+                        bitboard,
+                        depth,
+                        ai_is_player1,
+                        on_progress,
+                        cancelled,
+                        column,
+                    )
+                    .await?
+                }
+                (AiBackend::Minmax, None) => {
+                    search_in_slices(state, depth, ai_is_player1, on_progress, cancelled, column)
+                        .await?
+                }
+            };
+            last_ai_evaluation.set(Some(result.evaluation));
+            Ok(result
+                .best_action
+                .map_or(JsValue::UNDEFINED, |action| action.column.into()))
+        })
+    }
+
+    /// Stops the running `get_ai_move_async`, whose promise then rejects.
+    #[wasm_bindgen]
+    pub fn cancel_ai(&self) {
+        self.ai_cancelled.set(true);
+    }
+
+    fn start_async_search(&mut self) -> Rc<Cell<bool>> {
+        self.cancel_ai();
+        self.ai_cancelled = Rc::default();
+        Rc::clone(&self.ai_cancelled)
+    }
+
+    #[wasm_bindgen]
+    pub fn set_ai_backend(&mut self, backend: AiBackend) {
+        self.ai.backend = backend;
+    }
+
+    /// Whether the AI plays from the opening book, `Best` by default.
+    #[wasm_bindgen]
+    pub fn set_book_mode(&mut self, mode: BookMode) {
+        self.ai.book = mode;
+    }
+
+    /// Exploration constant and number of playouts of the MCTS backend for
+    /// `get_ai_move`. `get_ai_move_timed` plays out until the time is up.
+    #[wasm_bindgen]
+    pub fn set_mcts_options(&mut self, exploration: f32, max_iterations: u32) {
+        self.ai.mcts.exploration = exploration;
+        self.ai.mcts.max_iterations = max_iterations;
+    }
+
+    /// The AI searches on the bitboard representation, which is much faster
+    /// than the array board it mirrors. Only the classic board fits in it.
+    fn search_state(&self) -> Option<four_bitboard::FourRowBitboard> {
+        four_bitboard::FourRowBitboard::try_from(&self.state).ok()
+    }
+
+    /// Book moves only exist for the classic board, which is looked up as a
+    /// bitboard like it is searched.
+    fn book_move(&self) -> Option<four::GameAction> {
+        self.ai
+            .book_move(book::connect_four(), &self.search_state()?)
+    }
+
+    /// Book moves have no evaluation, so the last one is cleared.
+    fn choose_book_move(&mut self, action: four::GameAction) -> Option<u8> {
+        self.last_ai_evaluation.set(None);
+        log!("AI chose column {} from the opening book", action.column);
+        Some(action.column)
+    }
+
+    fn choose_move(&mut self, result: &minmax::SearchResult<four::GameAction>) -> Option<u8> {
+        // Store the evaluation for later retrieval
+        self.last_ai_evaluation.set(Some(result.evaluation));
+
+        let action = result.best_action?;
+        log!(
+            "AI chose column {} with evaluation {}",
+            action.column,
+            result.evaluation
+        );
+        Some(action.column)
+    }
+
+    #[wasm_bindgen]
+    pub fn get_last_ai_evaluation(&self) -> Option<f32> {
+        self.last_ai_evaluation.get()
+    }
+
+    /// Every legal move with its value after a `depth` ply search, best
+    /// first for the side to move, for hints. Returns an array of
+    /// `{ move, evaluation, principal_variation }` objects with moves as
+    /// columns and evaluations from Player1's point of view, like
+    /// `get_last_ai_evaluation`.
+    #[wasm_bindgen]
+    pub fn analyze(&self, depth: u8) -> Result<JsValue, JsValue> {
+        let maximizing_player = self.state.current_player == Player::Player1;
+        let column = |action: &four::GameAction| action.column;
+        match self.search_state() {
+            Some(bitboard) => {
+                move_hints(minmax::analyze(&bitboard, depth, maximizing_player), column)
+            }
+            None => move_hints(
+                minmax::analyze(&self.state, depth, maximizing_player),
+                column,
+            ),
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn reset(&mut self) {
+        self.state = four::FourRow::with_config(self.state.config);
+        self.cancel_ai();
+        self.last_ai_evaluation.set(None);
+        self.history = History::new((self.state.clone(), None));
+    }
+
+    /// Replaces the game with the position after a move string like
+    /// `"4453"` (1-based columns). The moves become the undo history.
+    #[wasm_bindgen]
+    pub fn load_moves(&mut self, moves: &str) -> Result<(), JsValue> {
+        four::FourRow::with_config(self.state.config)
+            .play_moves(moves)
+            .map_err(|err| JsValue::from_str(&err.to_string()))?;
+
+        self.reset();
+        for column in moves.trim().bytes() {
+            self.state = self.state.apply_action(&four::GameAction {
+                column: column - b'1',
+            });
+            self.history.push((self.state.clone(), None));
+        }
+        Ok(())
+    }
+
+    /// The current position as a move string, or an empty string if the
+    /// board cannot be reached by legal play.
+    #[wasm_bindgen]
+    pub fn export_moves(&self) -> String {
+        self.state.to_moves().unwrap_or_default()
+    }
+
+    /// Takes back the last ply. Returns false if there is nothing to undo.
+    #[wasm_bindgen]
+    pub fn undo(&mut self) -> bool {
+        self.step_back(1)
+    }
+
+    /// Takes back a full turn (the last two plies, e.g. the human move and
+    /// the AI reply), or a single ply at the start of the game.
+    #[wasm_bindgen]
+    pub fn undo_turn(&mut self) -> bool {
+        self.step_back(2)
+    }
+
+    /// Replays the last undone ply. Returns false if there is nothing to redo.
+    #[wasm_bindgen]
+    pub fn redo(&mut self) -> bool {
+        self.step_forward(1)
+    }
+
+    /// Replays up to two undone plies.
+    #[wasm_bindgen]
+    pub fn redo_turn(&mut self) -> bool {
+        self.step_forward(2)
+    }
+
+    #[wasm_bindgen]
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+
+    #[wasm_bindgen]
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+
+    /// Number of plies played to reach the current position.
+    #[wasm_bindgen]
+    pub fn history_len(&self) -> usize {
+        self.history.moves_played()
+    }
+
+    fn step_back(&mut self, plies: usize) -> bool {
+        let undone = self.history.undo(plies) > 0;
+        self.restore();
+        undone
+    }
+
+    fn step_forward(&mut self, plies: usize) -> bool {
+        let redone = self.history.redo(plies) > 0;
+        self.restore();
+        redone
+    }
+
+    fn restore(&mut self) {
+        let (state, evaluation) = self.history.current();
+        self.state = state.clone();
+        self.last_ai_evaluation.set(*evaluation);
+    }
+
+    /// Computes the exact outcome of the current position with perfect play.
+    ///
+    /// Returns a JSON object with `outcome` ("Win", "Loss" or "Draw" for the
+    /// side to move), `score` and `moves_to_end`. Early positions can take a
+    /// long time to solve. Only the classic 7x6 board can be solved.
+    #[wasm_bindgen]
+    pub fn solve(&mut self) -> Result<String, JsValue> {
+        let position = four_bitboard::FourRowBitboard::try_from(&self.state).map_err(
+            |four_bitboard::UnsupportedBoard(config)| {
+                JsValue::from_str(&format!(
+                    "the solver only supports the classic board, not {}x{} with {} in a row",
+                    config.width, config.height, config.win_length
+                ))
+            },
+        )?;
+        let solver = self.solver.get_or_insert_with(solver::Solver::new);
+        let solution = solver.solve(&position);
+        log!(
+            "Solved position in {} nodes: {:?}",
+            solver.node_count(),
+            solution
+        );
+        Ok(serde_json::to_string(&solution).unwrap_or_else(|_| "{}".to_string()))
+    }
+
+    /// Column of a move that keeps the solved outcome, playing perfectly.
+    /// Always `None` on boards other than the classic one.
+    #[wasm_bindgen]
+    pub fn get_perfect_move(&mut self) -> Option<u8> {
+        let position = self.search_state()?;
+        let solver = self.solver.get_or_insert_with(solver::Solver::new);
+        solver.best_move(&position).map(|action| action.column)
+    }
+
+    /// Height, width and the number of discs in a row needed to win.
+    #[wasm_bindgen]
+    pub fn get_board_dimensions(&self) -> Vec<usize> {
+        let config = self.state.config;
+        vec![config.height, config.width, config.win_length]
+    }
+}
+
+impl Default for ConnectFourGame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+pub struct CheckersGame {
+    state: checkers::CheckersBoard,
+    // Shared with async searches, which set it when they finish.
+    last_ai_evaluation: Rc<Cell<Option<f32>>>,
+    // Each position with the evaluation shown for it and the move that led
+    // there, which is `None` only for the first one.
+    history: History<(
+        checkers::CheckersBoard,
+        Option<f32>,
+        Option<<checkers::CheckersBoard as GameState>::Action>,
+    )>,
+    ai: AiSettings,
+    // Set to stop the running async search, replaced for every new one.
+    ai_cancelled: Rc<Cell<bool>>,
+    tablebase: Option<Arc<Tablebase>>,
+}
+
+#[wasm_bindgen]
+impl CheckersGame {
+    /// A game by the rules of `variant`, English checkers if it is left
+    /// out.
+    #[wasm_bindgen(constructor)]
+    pub fn new(variant: Option<Variant>) -> CheckersGame {
+        let rules = variant.unwrap_or(Variant::English).rules();
+        let state = checkers::CheckersBoard::new().with_rules(rules);
+        CheckersGame {
+            state: state.clone(),
+            last_ai_evaluation: Rc::default(),
+            history: History::new((state, None, None)),
+            ai: AiSettings::default(),
+            ai_cancelled: Rc::default(),
+            tablebase: None,
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn get_board(&self) -> String {
+        // Convert board to JSON format for web display
+        let mut board_json = Vec::new();
+        for row in 0..8 {
+            let mut row_data = Vec::new();
+            for col in 0..8 {
+                let tile = match self.state.board()[row][col] {
+                    checkers::CheckersTile::Empty => "Empty",
+                    checkers::CheckersTile::Checker(Player::Player1) => "Player1",
+                    checkers::CheckersTile::Checker(Player::Player2) => "Player2",
+                    checkers::CheckersTile::Queen(Player::Player1) => "Queen1",
+                    checkers::CheckersTile::Queen(Player::Player2) => "Queen2",
+                };
+                row_data.push(tile);
+            }
+            board_json.push(row_data);
+        }
+        serde_json::to_string(&board_json).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    #[wasm_bindgen]
+    pub fn get_current_player(&self) -> u8 {
+        match self.state.current_player() {
+            Player::Player1 => 1,
+            Player::Player2 => 2,
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn get_possible_moves(&self) -> String {
+        let actions = self.state.get_possible_actions();
+        serde_json::to_string(&actions).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Whether the moves of `get_possible_moves` are captures. Captures are
+    /// mandatory, so they are either all captures or none is.
+    #[wasm_bindgen]
+    pub fn must_capture(&self) -> bool {
+        !self.state.is_quiet()
+    }
+
+    #[wasm_bindgen]
+    pub fn make_move(&mut self, moves_json: &str) -> bool {
+        if let Ok(action) = serde_json::from_str::<Vec<(usize, usize, usize, usize)>>(moves_json) {
+            let possible_actions = self.state.get_possible_actions();
+            if possible_actions.contains(&action) {
+                self.state = self.state.apply_action(&action);
+                self.history.push((
+                    self.state.clone(),
+                    self.last_ai_evaluation.get(),
+                    Some(action),
+                ));
+                return true;
+            }
+        }
+        false
+    }
+
+    #[wasm_bindgen]
+    pub fn is_terminal(&self) -> bool {
+        self.state.is_terminal()
+    }
+
+    /// 0 while the game goes on, 1 or 2 for the winner, and for a draw the
+    /// rule that ended it: 3 if the side to move has no moves, 4 after 50
+    /// moves without a capture and 5 when a position occurred three times.
+    #[wasm_bindgen]
+    pub fn get_game_result(&self) -> u8 {
+        if !self.state.is_terminal() {
+            return 0; // Game ongoing
+        }
+
+        let player1_pieces = self.state.count_pieces(Player::Player1);
+        let player2_pieces = self.state.count_pieces(Player::Player2);
+
+        if player1_pieces == 0 {
+            return 2; // Player 2 wins
+        }
+        if player2_pieces == 0 {
+            return 1; // Player 1 wins
+        }
+
+        // Draw, by the rule that ended it
+        match self.state.draw_rule() {
+            Some(checkers::DrawRule::MoveLimit) => 4,
+            Some(checkers::DrawRule::Repetition) => 5,
+            _ => 3,
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn get_ai_move(&mut self, depth: u8, ai_is_player1: bool) -> String {
+        if let Some(action) = self.book_move() {
+            return self.choose_book_move(&action);
+        }
+        log!("AI is thinking with depth {}...", depth);
+
+        let maximizing_player = ai_is_player1;
+        let result = match self.tablebase_state() {
+            Some(state) => self.ai.search(&state, depth, maximizing_player),
+            None => self.ai.search(&self.state, depth, maximizing_player),
+        };
+
+        self.choose_move(&result)
+    }
+
+    /// Like `get_ai_move`, but searches ever deeper until `time_ms`
+    /// milliseconds have passed instead of to a fixed depth.
+    #[wasm_bindgen]
+    pub fn get_ai_move_timed(&mut self, time_ms: u32, ai_is_player1: bool) -> String {
+        if let Some(action) = self.book_move() {
+            return self.choose_book_move(&action);
+        }
+        log!("AI is thinking for {} ms...", time_ms);
+
+        let maximizing_player = ai_is_player1;
+        let budget = Duration::from_millis(time_ms as u64);
+        let result = match self.tablebase_state() {
+            Some(state) => self.ai.search_timed(&state, budget, maximizing_player),
+            None => self.ai.search_timed(&self.state, budget, maximizing_player),
+        };
+        log!("AI reached depth {}", result.depth);
+
+        self.choose_move(&result)
+    }
+
+    /// Like `get_ai_move`, but returns a `Promise` and searches in short
+    /// slices so the page stays responsive. `on_progress` is called with
+    /// `{ depth, best_move, evaluation }` after every completed depth, or
+    /// with the current best move after every slice of MCTS playouts.
+    ///
+    /// The promise resolves to the move as JSON, `"[]"` if there is none,
+    /// and rejects if `cancel_ai` or `reset` is called first.
+    #[wasm_bindgen]
+    pub fn get_ai_move_async(
+        &mut self,
+        depth: u8,
+        ai_is_player1: bool,
+        on_progress: Option<js_sys::Function>,
+    ) -> js_sys::Promise {
+        let cancelled = self.start_async_search();
+        if let Some(action) = self.book_move() {
+            let value: JsValue = self.choose_book_move(&action).into();
+            return js_sys::Promise::resolve(&value);
+        }
+        log!("AI is thinking with depth {}...", depth);
+
+        let last_ai_evaluation = Rc::clone(&self.last_ai_evaluation);
+        let (ai, state, tablebase_state) = (self.ai, self.state.clone(), self.tablebase_state());
+        future_to_promise(async move {
+            let result = match (ai.backend, tablebase_state) {
+                (AiBackend::Mcts, Some(state)) => {
+                    mcts_in_slices(
+                        state,
+                        ai_is_player1,
+                        ai.mcts,
+                        on_progress,
+                        cancelled,
+                        Clone::clone,
+                    )
+                    .await?
+                }
+                (AiBackend::Mcts, None) => {
+                    mcts_in_slices(
+                        state,
+                        ai_is_player1,
+                        ai.mcts,
+                        on_progress,
+                        cancelled,
+                        Clone::clone,
+                    )
+                    .await?
+                }
+                (AiBackend::Minmax, Some(state)) => {
+                    search_in_slices(
+                        state,
+                        depth,
+                        ai_is_player1,
+                        on_progress,
+                        cancelled,
+                        Clone::clone,
+                    )
+                    .await?
+                }
+                (AiBackend::Minmax, None) => {
+                    search_in_slices(
+                        state,
+                        depth,
+                        ai_is_player1,
+                        on_progress,
+                        cancelled,
+                        Clone::clone,
+                    )
+                    .await?
+                }
+            };
+            last_ai_evaluation.set(Some(result.evaluation));
+            let action = result.best_action.unwrap_or_default();
+            Ok(serde_json::to_string(&action)
+                .unwrap_or_else(|_| "[]".to_string())
+                .into())
+        })
+    }
+
+    /// Stops the running `get_ai_move_async`, whose promise then rejects.
+    #[wasm_bindgen]
+    pub fn cancel_ai(&self) {
+        self.ai_cancelled.set(true);
+    }
+
+    fn start_async_search(&mut self) -> Rc<Cell<bool>> {
+        self.cancel_ai();
+        self.ai_cancelled = Rc::default();
+        Rc::clone(&self.ai_cancelled)
+    }
+
+    #[wasm_bindgen]
+    pub fn set_ai_backend(&mut self, backend: AiBackend) {
+        self.ai.backend = backend;
+    }
+
+    /// Whether the AI plays from the opening book, `Best` by default.
+    #[wasm_bindgen]
+    pub fn set_book_mode(&mut self, mode: BookMode) {
+        self.ai.book = mode;
+    }
+
+    /// Exploration constant and number of playouts of the MCTS backend for
+    /// `get_ai_move`. `get_ai_move_timed` plays out until the time is up.
+    #[wasm_bindgen]
+    pub fn set_mcts_options(&mut self, exploration: f32, max_iterations: u32) {
+        self.ai.mcts.exploration = exploration;
+        self.ai.mcts.max_iterations = max_iterations;
+    }
+
+    /// Loads an endgame tablebase written by `fourrow tablebase`. The AI
+    /// then plays the positions it covers perfectly instead of searching
+    /// them.
+    #[wasm_bindgen]
+    pub fn load_tablebase(&mut self, bytes: &[u8]) -> Result<(), JsValue> {
+        let tablebase =
+            Tablebase::from_bytes(bytes).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        log!(
+            "Loaded tablebase for up to {} pieces",
+            tablebase.max_pieces()
+        );
+        self.tablebase = Some(Arc::new(tablebase));
+        Ok(())
+    }
+
+    /// The opening book only holds English checkers positions.
+    fn book_move(&self) -> Option<<checkers::CheckersBoard as GameState>::Action> {
+        if self.state.rules() != checkers::RulesConfig::ENGLISH {
+            return None;
+        }
+        self.ai.book_move(book::checkers(), &self.state)
+    }
+
+    /// The position to search when a tablebase is loaded.
+    fn tablebase_state(&self) -> Option<TablebaseBoard> {
+        let tablebase = self.tablebase.as_ref()?;
+        Some(TablebaseBoard {
+            board: self.state.clone(),
+            tablebase: Arc::clone(tablebase),
+        })
+    }
+
+    /// Book moves have no evaluation, so the last one is cleared.
+    fn choose_book_move(
+        &mut self,
+        action: &<checkers::CheckersBoard as GameState>::Action,
+    ) -> String {
+        self.last_ai_evaluation.set(None);
+        log!(
+            "AI chose {} from the opening book",
+            pdn::format_move(&self.state, action)
+        );
+        serde_json::to_string(action).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    fn choose_move(
+        &mut self,
+        result: &minmax::SearchResult<<checkers::CheckersBoard as GameState>::Action>,
+    ) -> String {
+        // Store the evaluation for later retrieval
+        self.last_ai_evaluation.set(Some(result.evaluation));
+
+        match &result.best_action {
+            Some(action) => {
+                log!("AI chose move with evaluation {}", result.evaluation);
+                serde_json::to_string(action).unwrap_or_else(|_| "[]".to_string())
+            }
+            None => "[]".to_string(),
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn get_last_ai_evaluation(&self) -> Option<f32> {
+        self.last_ai_evaluation.get()
+    }
+
+    /// Every legal move with its value after a `depth` ply search, best
+    /// first for the side to move, for hints. Returns an array of
+    /// `{ move, evaluation, principal_variation }` objects with moves as
+    /// arrays of jumps like `get_possible_moves` and evaluations from
+    /// Player1's point of view, like `get_last_ai_evaluation`.
+    #[wasm_bindgen]
+    pub fn analyze(&self, depth: u8) -> Result<JsValue, JsValue> {
+        let maximizing_player = self.state.current_player() == Player::Player1;
+        let analysis = match self.tablebase_state() {
+            Some(state) => minmax::analyze(&state, depth, maximizing_player),
+            None => minmax::analyze(&self.state, depth, maximizing_player),
+        };
+        move_hints(analysis, Clone::clone)
+    }
+
+    #[wasm_bindgen]
+    pub fn reset(&mut self) {
+        self.start_from(checkers::CheckersBoard::new().with_rules(self.state.rules()));
+    }
+
+    /// Replaces the game with the one in a PDN string, played by the rules
+    /// of its `GameType` header. The moves become the undo history.
+    #[wasm_bindgen]
+    pub fn load_pdn(&mut self, pdn: &str) -> Result<(), JsValue> {
+        let game = pdn::PdnGame::parse(pdn).map_err(|err| JsValue::from_str(&err.to_string()))?;
+
+        self.start_from(game.start);
+        for action in &game.moves {
+            self.state = self.state.apply_action(action);
+            self.history
+                .push((self.state.clone(), None, Some(action.clone())));
+        }
+        Ok(())
+    }
+
+    /// The moves played so far as a PDN string. Fails for variants PDN
+    /// has no game type for here, see `pdn::game_type`.
+    #[wasm_bindgen]
+    pub fn export_pdn(&self) -> Result<String, JsValue> {
+        if pdn::game_type(self.state.rules()).is_none() {
+            return Err(JsValue::from_str("PDN cannot record games of this variant"));
+        }
+        let (start, plies) = self
+            .history
+            .played()
+            .split_first()
+            .expect("the history starts with a position");
+        let moves = plies
+            .iter()
+            .map(|(_, _, action)| {
+                action
+                    .clone()
+                    .ok_or_else(|| JsValue::from_str("a ply of the game was not recorded"))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(pdn::PdnGame::new(start.0.clone(), moves).to_pdn())
+    }
+
+    /// Sets up a position from draughts FEN, e.g. `W:W21,22,K5:B1,2`,
+    /// clearing the undo history. The rules stay the same.
+    #[wasm_bindgen]
+    pub fn set_position(&mut self, fen: &str) -> Result<(), JsValue> {
+        let state = pdn::parse_fen(fen).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        self.start_from(state.with_rules(self.state.rules()));
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn get_fen(&self) -> String {
+        pdn::to_fen(&self.state)
+    }
+
+    fn start_from(&mut self, state: checkers::CheckersBoard) {
+        self.state = state;
+        self.cancel_ai();
+        self.last_ai_evaluation.set(None);
+        self.history = History::new((self.state.clone(), None, None));
+    }
+
+    /// Takes back the last ply. Returns false if there is nothing to undo.
+    #[wasm_bindgen]
+    pub fn undo(&mut self) -> bool {
+        self.step_back(1)
+    }
+
+    /// Takes back a full turn (the last two plies, e.g. the human move and
+    /// the AI reply), or a single ply at the start of the game.
+    #[wasm_bindgen]
+    pub fn undo_turn(&mut self) -> bool {
+        self.step_back(2)
+    }
+
+    /// Replays the last undone ply. Returns false if there is nothing to redo.
+    #[wasm_bindgen]
+    pub fn redo(&mut self) -> bool {
+        self.step_forward(1)
+    }
+
+    /// Replays up to two undone plies.
+    #[wasm_bindgen]
+    pub fn redo_turn(&mut self) -> bool {
+        self.step_forward(2)
+    }
+
+    #[wasm_bindgen]
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+
+    #[wasm_bindgen]
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+
+    /// Number of plies played to reach the current position.
+    #[wasm_bindgen]
+    pub fn history_len(&self) -> usize {
+        self.history.moves_played()
+    }
+
+    fn step_back(&mut self, plies: usize) -> bool {
+        let undone = self.history.undo(plies) > 0;
+        self.restore();
+        undone
+    }
+
+    fn step_forward(&mut self, plies: usize) -> bool {
+        let redone = self.history.redo(plies) > 0;
+        self.restore();
+        redone
+    }
+
+    fn restore(&mut self) {
+        let (state, evaluation, _) = self.history.current();
+        self.state = state.clone();
+        self.last_ai_evaluation.set(*evaluation);
+    }
+
+    #[wasm_bindgen]
+    pub fn get_moves_without_capture(&self) -> u32 {
+        self.state.moves_without_capture
+    }
+}
+
+impl Default for CheckersGame {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}